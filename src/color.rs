@@ -0,0 +1,127 @@
+//! Colored output, bytes are colored by class like hexyl
+//!
+//! Every conversion that displays input bytes is wrapped in the ANSI color of
+//! the class of its bytes, in the numeric columns and the ASCII column alike.
+//! The hex digits of a word of bytes of different classes are colored byte
+//! by byte, other conversions of mixed bytes aren't colored.
+use crate::format::Endian;
+use std::io::IsTerminal;
+
+/// ANSI sequence that goes back to the default color
+pub const RESET: &str = "\x1b[0m";
+
+/// ANSI sequence for bytes picked out by --find, reverse video
+pub const MARK: &str = "\x1b[7m";
+
+/// The kinds of byte that get their own color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ByteClass {
+    /// 0x00
+    Null,
+    /// Printable ASCII, 0x21 - 0x7e
+    Printable,
+    /// Space, tab, newline, vertical tab, form feed and carriage return
+    Whitespace,
+    /// The other ASCII control characters and DEL
+    Control,
+    /// 0x80 - 0xfe
+    HighBit,
+    /// 0xff
+    Ff,
+}
+
+impl ByteClass {
+    /// The ANSI sequence that starts the color of the class
+    pub fn color(&self) -> &'static str {
+        match self {
+            ByteClass::Null => "\x1b[90m",
+            ByteClass::Printable => "\x1b[36m",
+            ByteClass::Whitespace => "\x1b[32m",
+            ByteClass::Control => "\x1b[35m",
+            ByteClass::HighBit => "\x1b[33m",
+            ByteClass::Ff => "\x1b[31m",
+        }
+    }
+}
+
+/// The class of a byte
+pub fn byte_class(byte: u8) -> ByteClass {
+    match byte {
+        0x00 => ByteClass::Null,
+        b' ' | b'\t' | b'\n' | 0x0b | 0x0c | b'\r' => ByteClass::Whitespace,
+        0x21..=0x7e => ByteClass::Printable,
+        0x01..=0x1f | 0x7f => ByteClass::Control,
+        0xff => ByteClass::Ff,
+        _ => ByteClass::HighBit,
+    }
+}
+
+/// The color of the bytes displayed by one conversion
+///
+/// Args:
+///   bytes - The bytes of the conversion, e.g. both bytes of a -x word
+/// Return:
+///   The ANSI sequence of the class of the bytes, or None if they are of
+///   different classes
+pub fn unit_color(bytes: &[u8]) -> Option<&'static str> {
+    let (first, rest) = bytes.split_first()?;
+    let class = byte_class(*first);
+    rest.iter()
+        .all(|byte| byte_class(*byte) == class)
+        .then(|| class.color())
+}
+
+/// Append a hex conversion with each pair of digits in the color of its byte
+///
+/// Args:
+///   out - Buffer the colored text is appended to
+///   text - The conversion as written, its last hex digits are the value
+///   bytes - The bytes of the conversion, shorter than size at the end of input
+///   size - The size of the value in bytes
+///   endian - The byte order of the value
+pub fn write_hex_bytes(out: &mut Vec<u8>, text: &[u8], bytes: &[u8], size: usize, endian: Endian) {
+    let end = text
+        .iter()
+        .rposition(|c| c.is_ascii_hexdigit())
+        .map_or(0, |last| last + 1);
+    let digits = text[..end]
+        .iter()
+        .rev()
+        .take_while(|c| c.is_ascii_hexdigit())
+        .count()
+        .min(2 * size);
+    out.extend_from_slice(&text[..end - digits]);
+    let mut current = None;
+    for (i, c) in text[..end].iter().enumerate().skip(end - digits) {
+        // Two digits for each byte, the least significant byte last
+        let significance = (end - 1 - i) / 2;
+        let index = match endian {
+            Endian::Little => significance,
+            Endian::Big => size - 1 - significance,
+        };
+        let color = bytes.get(index).map(|byte| byte_class(*byte).color());
+        if color != current {
+            if current.is_some() {
+                out.extend_from_slice(RESET.as_bytes());
+            }
+            if let Some(color) = color {
+                out.extend_from_slice(color.as_bytes());
+            }
+            current = color;
+        }
+        out.push(*c);
+    }
+    if current.is_some() {
+        out.extend_from_slice(RESET.as_bytes());
+    }
+    out.extend_from_slice(&text[end..]);
+}
+
+/// Work out whether --color=auto colors the output
+///
+/// Return:
+///   true if stdout is a terminal and NO_COLOR isn't set to something
+pub fn auto() -> bool {
+    std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
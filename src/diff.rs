@@ -0,0 +1,247 @@
+//! --diff, compare two inputs side by side
+//!
+//! Both inputs are read a line at a time.  Lines that differ are shown next
+//! to each other and runs of identical lines are squeezed into a single '*'.
+//! The differing bytes are highlighted when colors are on, and marked with
+//! '^' on a line of their own when they are off.  The summary lists the
+//! ranges of differing bytes instead.
+use crate::error::HdError;
+use crate::format::{self, Format};
+use crate::{read_error, read_full, vecs_match};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+/// Options for --diff
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffOptions {
+    /// The names of the two inputs, shown above the dump and in the summary
+    pub names: [String; 2],
+    /// Bytes of each input per line
+    pub width: usize,
+    /// --summary list the ranges of differing bytes instead of the dump
+    pub summary: bool,
+    /// -v show identical lines instead of squeezing them
+    pub no_squeezing: bool,
+    /// Color the bytes by class and highlight the bytes that differ
+    pub color: bool,
+}
+
+/// The formats of the left and right side of a line
+fn side_formats(width: usize, color: bool) -> [Format; 2] {
+    // The left ASCII column is padded so the right side lines up
+    let sides = [("%1_p", "|  "), ("%_p", "|\n")];
+    sides.map(|(printable, end)| {
+        let strings = [
+            format!(r#"{}/1 "%02x ""#, width),
+            format!(r#""  |" {}/1 "{}" "{}""#, width, printable, end),
+        ];
+        let strings =
+            format::parse_format_strings(&strings).expect("diff format strings are valid");
+        Format::new(strings).with_color(color)
+    })
+}
+
+/// Compare two inputs and show where they differ
+///
+/// Args:
+///   options - The names, width and kind of output
+///   inputs - The two inputs, already positioned at the first byte to compare
+///   writer - The sink the comparison is written to
+///   offset - The address of the first byte read from the inputs
+///   length - Number of bytes to compare, None for everything up to the end
+/// Return:
+///   Result(bytes) the number of bytes that differ or are only in one input,
+///   0 if they are the same, or Err(HdError) if reading or writing fails
+pub fn diff<A: Read, B: Read, W: Write>(
+    options: &DiffOptions,
+    inputs: (A, B),
+    mut writer: W,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<usize, HdError> {
+    let (mut a, mut b) = inputs;
+    let width = options.width.max(1);
+    let formats = side_formats(width, options.color);
+    let mut lines = [vec![0u8; width], vec![0u8; width]];
+    let mut totals = [0usize; 2];
+    let mut marked: Vec<bool> = Vec::with_capacity(width);
+    let mut text: Vec<u8> = Vec::new();
+    let mut left = length.unwrap_or(usize::MAX);
+    let mut address = offset;
+    let mut differences = 0;
+    // Start of the range of differing bytes being built for the summary
+    let mut range: Option<usize> = None;
+    let mut squeezed = false;
+    if !options.summary {
+        let pad = 4 * width + 5;
+        let header = format!("{:10}{:pad$}{}\n", "", options.names[0], options.names[1]);
+        writer
+            .write_all(header.as_bytes())
+            .map_err(HdError::Write)?;
+    }
+    while left > 0 {
+        let want = width.min(left);
+        let lens = [
+            read_full(&mut a, &mut lines[0][..want]).map_err(read_error)?,
+            read_full(&mut b, &mut lines[1][..want]).map_err(read_error)?,
+        ];
+        totals = [totals[0] + lens[0], totals[1] + lens[1]];
+        let longest = lens[0].max(lens[1]);
+        if longest == 0 {
+            break;
+        }
+        let [line_a, line_b] = [&lines[0][..lens[0]], &lines[1][..lens[1]]];
+        marked.clear();
+        marked.extend((0..longest).map(|i| line_a.get(i) != line_b.get(i)));
+        let differing = marked.iter().filter(|m| **m).count();
+        differences += differing;
+
+        if options.summary {
+            // Bytes that are in only one input are reported at the end
+            let shortest = lens[0].min(lens[1]);
+            for (i, differs) in marked[..shortest].iter().enumerate() {
+                match (differs, range) {
+                    (true, None) => range = Some(address + i),
+                    (false, Some(start)) => {
+                        write_range(&mut writer, start, address + i, "differ")?;
+                        range = None;
+                    }
+                    _ => {}
+                }
+            }
+        } else if differing == 0 && !options.no_squeezing && vecs_match(line_a, line_b) {
+            if !squeezed {
+                writer.write_all(b"*\n").map_err(HdError::Write)?;
+                squeezed = true;
+            }
+        } else {
+            squeezed = false;
+            text.clear();
+            text.extend_from_slice(format!("{:08x}  ", address).as_bytes());
+            if options.color {
+                formats[0].display_marked(&mut text, line_a, address, &marked);
+                formats[1].display_marked(&mut text, line_b, address, &marked);
+            } else {
+                formats[0].display(&mut text, line_a, address);
+                formats[1].display(&mut text, line_b, address);
+                if differing > 0 {
+                    write_carets(&mut text, width, &marked, lens);
+                }
+            }
+            writer.write_all(&text).map_err(HdError::Write)?;
+        }
+        address += longest;
+        left -= longest;
+        if longest < want {
+            break;
+        }
+    }
+    if options.summary {
+        let shortest = offset + totals[0].min(totals[1]);
+        if let Some(start) = range {
+            write_range(&mut writer, start, shortest, "differ")?;
+        }
+        if totals[0] != totals[1] {
+            let longer = &options.names[usize::from(totals[1] > totals[0])];
+            write_range(
+                &mut writer,
+                shortest,
+                address,
+                &format!("only in {}", longer),
+            )?;
+        }
+    }
+    writer.flush().map_err(HdError::Write)?;
+    Ok(differences)
+}
+
+/// Add a line with '^' under the differing bytes of a line, for no colors
+///
+/// Args:
+///   text - The line of the dump, the carets are added after it
+///   width - Bytes of each input per line
+///   marked - true for each byte of the line that differs
+///   lens - Number of bytes of the line in each input
+fn write_carets(text: &mut Vec<u8>, width: usize, marked: &[bool], lens: [usize; 2]) {
+    // The columns of the hex and ASCII bytes of each side, after the address
+    // and two spaces, the hex and "  |", the ASCII and "|  "
+    let hex = [10, 15 + 4 * width];
+    let ascii = [12 + 3 * width, 17 + 7 * width];
+    let mut carets = vec![b' '; ascii[1] + width];
+    for (i, _) in marked.iter().enumerate().filter(|(_, differs)| **differs) {
+        for side in 0..2 {
+            if i < lens[side] {
+                let column = hex[side] + 3 * i;
+                carets[column..column + 2].copy_from_slice(b"^^");
+                carets[ascii[side] + i] = b'^';
+            }
+        }
+    }
+    let len = carets
+        .iter()
+        .rposition(|c| *c != b' ')
+        .map_or(0, |last| last + 1);
+    text.extend_from_slice(&carets[..len]);
+    text.push(b'\n');
+}
+
+/// Print a range of differing bytes for the summary
+///
+/// Args:
+///   writer - The sink the summary is written to
+///   start - Address of the first byte of the range
+///   end - Address just past the range
+///   what - How the bytes differ, e.g. "differ" or "only in a.bin"
+fn write_range<W: Write>(
+    writer: &mut W,
+    start: usize,
+    end: usize,
+    what: &str,
+) -> Result<(), HdError> {
+    let count = end - start;
+    let bytes = if count == 1 { "byte" } else { "bytes" };
+    writeln!(
+        writer,
+        "{:08x}-{:08x}  {} {} {}",
+        start,
+        end - 1,
+        count,
+        bytes,
+        what
+    )
+    .map_err(HdError::Write)
+}
+
+/// Open an input for --diff, "-" for standard input, and skip offset bytes
+fn open(path: &str, offset: usize) -> Result<Box<dyn Read>, HdError> {
+    let mut input: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(path).map_err(|err| HdError::from_io(path, err))?;
+        Box::new(BufReader::new(file))
+    };
+    io::copy(&mut (&mut input).take(offset as u64), &mut io::sink())
+        .map_err(|err| HdError::from_io(path, err))?;
+    Ok(input)
+}
+
+/// Open two files and compare them
+///
+/// Args:
+///   options - The names of the files, the width and kind of output
+///   offset - Bytes to skip at the start of both files
+///   length - Number of bytes to compare, None for everything up to the end
+///   writer - The sink the comparison is written to
+/// Return:
+///   Result(bytes) the number of bytes that differ or are only in one file,
+///   or Err(HdError) if a file can't be opened or read or writing fails
+pub fn diff_files<W: Write>(
+    options: &DiffOptions,
+    offset: usize,
+    length: Option<usize>,
+    writer: W,
+) -> Result<usize, HdError> {
+    let a = open(&options.names[0], offset)?;
+    let b = open(&options.names[1], offset)?;
+    diff(options, (a, b), writer, offset, length)
+}
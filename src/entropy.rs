@@ -0,0 +1,177 @@
+//! --entropy and --histogram, how the byte values of the input are spread
+//!
+//! The Shannon entropy of the whole input tells compressed or encrypted data
+//! (close to 8 bits per byte) from text, code and padding.  The entropy of
+//! each block is drawn as a bar so the regions of a file stand out, and the
+//! histogram counts every byte value.
+use crate::error::HdError;
+use crate::{DumpSummary, READ_BUF_LEN, read_error, read_full};
+use std::io::{Read, Write};
+
+/// Block size of the entropy graph, 4 KiB
+pub const DEFAULT_BLOCK_SIZE: usize = 4096;
+
+/// Columns of a full bar, for 8 bits per byte or the most common byte value
+const BAR_WIDTH: usize = 32;
+
+/// Options for --entropy and --histogram
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntropyOptions {
+    /// --entropy print the entropy of the input and of each block
+    pub entropy: bool,
+    /// --histogram print the count of each byte value
+    pub histogram: bool,
+    /// --block-size bytes per row of the entropy graph
+    pub block_size: usize,
+}
+
+/// Shannon entropy of a histogram
+///
+/// Args:
+///   counts - Number of times each byte value occurs
+/// Return:
+///   The entropy in bits per byte, 0.0 for no bytes to 8.0 when every byte
+///   value is equally common
+pub fn shannon_entropy(counts: &[usize; 256]) -> f64 {
+    let total: usize = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// A bar of eighth blocks, value out of full gets BAR_WIDTH columns
+fn bar(value: f64, full: f64) -> String {
+    const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    if full <= 0.0 {
+        return String::new();
+    }
+    let eighths = (value / full * (BAR_WIDTH * 8) as f64).round() as usize;
+    let mut text = "█".repeat(eighths / 8);
+    if eighths % 8 != 0 {
+        text.push(EIGHTHS[eighths % 8]);
+    }
+    text
+}
+
+/// Print the entropy of a block as a row of the graph
+fn write_block<W: Write>(
+    writer: &mut W,
+    address: usize,
+    counts: &[usize; 256],
+) -> Result<(), HdError> {
+    let entropy = shannon_entropy(counts);
+    let line = format!("{:08x}  {:5.3}  {}", address, entropy, bar(entropy, 8.0));
+    writeln!(writer, "{}", line.trim_end()).map_err(HdError::Write)
+}
+
+/// Count the byte values of the input and print how they are spread
+///
+/// With --entropy a row per block with the entropy of the block as a bar is
+/// printed as soon as the block is read, then the total and the entropy of
+/// the input.  With --histogram a row per byte value that occurs with its
+/// count, share and a bar scaled to the most common value follows.
+///
+/// Args:
+///   options - What to print and the block size of the graph
+///   reader - The input, already positioned at the first byte to count
+///   writer - The sink the report is written to
+///   offset - The address of the first byte read from reader
+///   length - Number of bytes to count, None for everything up to the end
+/// Return:
+///   Result(DumpSummary) with the lines printed, or Err(HdError) if reading
+///   or writing fails
+pub fn dump_entropy<R: Read, W: Write>(
+    options: &EntropyOptions,
+    mut reader: R,
+    mut writer: W,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<DumpSummary, HdError> {
+    let block_size = options.block_size.max(1);
+    let mut counts = [0usize; 256];
+    let mut block_counts = [0usize; 256];
+    let mut block_len = 0;
+    let mut input = vec![0u8; READ_BUF_LEN];
+    let mut left = length.unwrap_or(usize::MAX);
+    let mut address = offset;
+    let mut lines_printed = 0;
+    while left > 0 {
+        let want = READ_BUF_LEN.min(left);
+        let bytes_read = read_full(&mut reader, &mut input[..want]).map_err(read_error)?;
+        for &byte in &input[..bytes_read] {
+            counts[byte as usize] += 1;
+            if options.entropy {
+                block_counts[byte as usize] += 1;
+                block_len += 1;
+                if block_len == block_size {
+                    write_block(&mut writer, address + 1 - block_len, &block_counts)?;
+                    lines_printed += 1;
+                    block_counts = [0; 256];
+                    block_len = 0;
+                }
+            }
+            address += 1;
+        }
+        left -= bytes_read;
+        if bytes_read < want {
+            break;
+        }
+    }
+    let total = address - offset;
+    if options.entropy {
+        if block_len > 0 {
+            write_block(&mut writer, address - block_len, &block_counts)?;
+            lines_printed += 1;
+        }
+        if total > 0 {
+            writer.write_all(b"\n").map_err(HdError::Write)?;
+            lines_printed += 1;
+        }
+        let distinct = counts.iter().filter(|count| **count > 0).count();
+        let totals = format!(
+            "bytes     {}\nentropy   {:.3} bits per byte\ndistinct  {} byte values\n",
+            total,
+            shannon_entropy(&counts),
+            distinct
+        );
+        writer
+            .write_all(totals.as_bytes())
+            .map_err(HdError::Write)?;
+        lines_printed += 3;
+    }
+    if options.histogram {
+        if options.entropy {
+            writer.write_all(b"\n").map_err(HdError::Write)?;
+            lines_printed += 1;
+        }
+        let most = counts.iter().copied().max().unwrap_or(0) as f64;
+        for (byte, count) in counts.iter().enumerate().filter(|(_, count)| **count > 0) {
+            let share = 100.0 * *count as f64 / total as f64;
+            let line = format!(
+                "{:02x}  {:10}  {:6.2}%  {}",
+                byte,
+                count,
+                share,
+                bar(*count as f64, most)
+            );
+            writeln!(writer, "{}", line.trim_end()).map_err(HdError::Write)?;
+            lines_printed += 1;
+        }
+    }
+    writer.flush().map_err(HdError::Write)?;
+    Ok(DumpSummary {
+        bytes_dumped: total,
+        end_address: address,
+        lines_printed,
+        lines_squeezed: 0,
+    })
+}
@@ -0,0 +1,164 @@
+//! Errors returned by hd and the exit codes they map to
+//!
+//! | Exit code | Meaning                                        |
+//! |-----------|------------------------------------------------|
+//! | 0         | the dump succeeded                             |
+//! | 1         | reading the input failed                       |
+//! | 2         | invalid command line arguments                 |
+//! | 3         | a file does not exist                          |
+//! | 4         | permission denied opening or reading a file    |
+//! | 5         | the skip offset is past the end of the input   |
+//! | 6         | a file got shorter while it was being read     |
+//! | 7         | writing the dump failed                        |
+//! | 8         | a format string or format file is invalid      |
+//! | 9         | the dump given to -r can't be parsed           |
+//! | 10        | the inputs given to --diff are different       |
+use crate::format::FormatError;
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+
+/// Exit code for a successful dump
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code when reading the input failed
+pub const EXIT_READ: i32 = 1;
+/// Exit code for invalid command line arguments
+pub const EXIT_USAGE: i32 = 2;
+/// Exit code when a file does not exist
+pub const EXIT_MISSING_PATH: i32 = 3;
+/// Exit code when a file can't be opened or read because of its permissions
+pub const EXIT_PERMISSION_DENIED: i32 = 4;
+/// Exit code when the skip offset is past the end of the input
+pub const EXIT_OFFSET_PAST_END: i32 = 5;
+/// Exit code when a file got shorter while it was being read
+pub const EXIT_SHORT_READ: i32 = 6;
+/// Exit code when writing the dump failed
+pub const EXIT_WRITE: i32 = 7;
+/// Exit code when a format string or format file is invalid
+pub const EXIT_FORMAT: i32 = 8;
+/// Exit code when the dump given to -r can't be parsed
+pub const EXIT_BAD_DUMP: i32 = 9;
+/// Exit code when the inputs given to --diff are different, it is 0 when
+/// they are the same
+pub const EXIT_DIFFERENT: i32 = 10;
+
+/// Everything that can go wrong while dumping
+#[derive(Debug)]
+pub enum HdError {
+    /// A file does not exist
+    MissingPath(String),
+    /// A file can't be opened or read because of its permissions
+    PermissionDenied(String),
+    /// The skip offset is past the end of the input
+    OffsetPastEnd { offset: usize, input_length: usize },
+    /// A file got shorter while it was being read
+    ShortRead {
+        path: String,
+        expected: u64,
+        read: u64,
+    },
+    /// Reading the input failed, path is None for a plain Read source
+    Read {
+        path: Option<String>,
+        source: io::Error,
+    },
+    /// Writing the dump failed
+    Write(io::Error),
+    /// A -e format string or a -f format file is invalid
+    Format { source: String, error: FormatError },
+    /// A line of the dump given to -r can't be parsed or goes backwards
+    BadDump { line: usize, message: String },
+    /// One or more inputs were skipped because they couldn't be read
+    Inputs(Vec<HdError>),
+}
+
+impl HdError {
+    /// Build the error for an input file that couldn't be opened or read
+    ///
+    /// Args:
+    ///   path - The file that failed
+    ///   err - The error returned by the OS
+    /// Return:
+    ///   MissingPath, PermissionDenied or Read depending on the error kind
+    pub fn from_io(path: &str, err: io::Error) -> HdError {
+        match err.kind() {
+            ErrorKind::NotFound => HdError::MissingPath(path.to_string()),
+            ErrorKind::PermissionDenied => HdError::PermissionDenied(path.to_string()),
+            _ => HdError::Read {
+                path: Some(path.to_string()),
+                source: err,
+            },
+        }
+    }
+
+    /// The process exit code for this error, see the table in the module docs
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HdError::MissingPath(_) => EXIT_MISSING_PATH,
+            HdError::PermissionDenied(_) => EXIT_PERMISSION_DENIED,
+            HdError::OffsetPastEnd { .. } => EXIT_OFFSET_PAST_END,
+            HdError::ShortRead { .. } => EXIT_SHORT_READ,
+            HdError::Read { .. } => EXIT_READ,
+            HdError::Write(_) => EXIT_WRITE,
+            HdError::Format { .. } => EXIT_FORMAT,
+            HdError::BadDump { .. } => EXIT_BAD_DUMP,
+            HdError::Inputs(errors) => errors.first().map_or(EXIT_READ, HdError::exit_code),
+        }
+    }
+}
+
+impl fmt::Display for HdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HdError::MissingPath(path) => write!(f, "{}: No such file or directory", path),
+            HdError::PermissionDenied(path) => write!(f, "{}: Permission denied", path),
+            HdError::OffsetPastEnd {
+                offset,
+                input_length,
+            } => write!(
+                f,
+                "offset is past the end of the input ({} > {})",
+                offset, input_length
+            ),
+            HdError::ShortRead {
+                path,
+                expected,
+                read,
+            } => write!(
+                f,
+                "{}: file shrank while reading, read {} of {} bytes",
+                path, read, expected
+            ),
+            HdError::Read {
+                path: Some(path),
+                source,
+            } => write!(f, "{}: {}", path, source),
+            HdError::Read { path: None, source } => write!(f, "read failure: {}", source),
+            HdError::Write(source) => write!(f, "write failure: {}", source),
+            HdError::Format { source, error } => match error.file {
+                Some(_) => write!(f, "bad format file, {}", error),
+                None => write!(f, "bad format string '{}', {}", source, error),
+            },
+            HdError::BadDump { line, message } => write!(f, "bad dump, line {}: {}", line, message),
+            HdError::Inputs(errors) => {
+                for (i, err) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for HdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HdError::Read { source, .. } | HdError::Write(source) => Some(source),
+            HdError::Format { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,1430 @@
+//! hexdump(1) style format strings
+//!
+//! A format string is a list of format units.  Each unit is an optional
+//! iteration count and byte count followed by a quoted printf style format:
+//!
+//!   `"%07.7_ax " 8/2 "%04x " "\n"`
+//!
+//! The input is processed in blocks, where a block is the largest number of
+//! bytes consumed by any of the format strings.  Every format string is
+//! applied to every block, so several format strings print several rows for
+//! the same block of input.
+use crate::color;
+use std::fmt;
+
+/// Bytes per line of the built-in display modes when no width is given
+pub const DEFAULT_WIDTH: usize = 16;
+/// Bytes per line of --binary when no width is given, so that a line with
+/// nibbles and an ASCII column fits in 120 columns
+pub const DEFAULT_BINARY_WIDTH: usize = 8;
+
+/// The built-in display modes, each one is a set of format strings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Two-byte hexadecimal display when no mode is given
+    Default,
+    /// -b, one-byte octal display
+    OneByteOctal,
+    /// -c, one-byte character display
+    OneByteChar,
+    /// -C, canonical hex+ASCII display
+    Canonical,
+    /// -d, two-bytes decimal display
+    TwoBytesDec,
+    /// -o, two-bytes octal display
+    TwoBytesOctal,
+    /// -x, two-bytes hexadecimal display
+    TwoBytesHex,
+    /// -t, integers of 1, 2, 4, 8 or 16 bytes like od -t x4
+    Int(IntKind, usize),
+    /// -t f, IEEE-754 floats of 2, 4 or 8 bytes and the significant digits
+    /// shown
+    Float(usize, usize),
+    /// --binary, every byte as 8 bits, with a space between nibbles and an
+    /// ASCII column if asked for
+    Binary { nibbles: bool, ascii: bool },
+}
+
+/// How -t integers are displayed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntKind {
+    /// -t x, zero padded hexadecimal
+    Hex,
+    /// -t d, signed decimal
+    Signed,
+    /// -t u, unsigned decimal
+    Unsigned,
+    /// -t o, zero padded octal
+    Octal,
+}
+
+/// Group sizes accepted by Mode::format_strings
+pub const GROUP_SIZES: [usize; 5] = [1, 2, 4, 8, 16];
+
+/// Sizes in bytes accepted by -t f, half, single and double precision
+pub const FLOAT_SIZES: [usize; 3] = [2, 4, 8];
+
+/// Significant digits shown for a float of a size in bytes when no precision
+/// is given, enough for every value to read back the same
+pub fn float_precision(size: usize) -> usize {
+    match size {
+        2 => 5,
+        4 => 9,
+        _ => 17,
+    }
+}
+
+/// Digits needed for the largest value of a size in bytes, in octal and decimal
+fn max_digits(size: usize, radix: u32) -> usize {
+    match radix {
+        8 => (8 * size).div_ceil(3),
+        _ => match size {
+            1 => 3,
+            2 => 5,
+            4 => 10,
+            8 => 20,
+            _ => 39,
+        },
+    }
+}
+
+/// Characters needed for the most negative signed value of a size in bytes
+fn max_signed_len(size: usize) -> usize {
+    match size {
+        1 => 4,
+        2 => 6,
+        4 => 11,
+        8 => 20,
+        _ => 40,
+    }
+}
+
+impl std::str::FromStr for Mode {
+    type Err = String;
+
+    /// Parse an od style -t type, a letter followed by an optional size in
+    /// bytes, 4 by default for integers and 8 for floats, e.g. x4, d8, u or f4.
+    /// Like od, the integer sizes can also be given as C (char), S (short),
+    /// I (int) or L (long), e.g. dC for signed bytes
+    fn from_str(spec: &str) -> Result<Mode, String> {
+        let bad = || {
+            format!(
+                "invalid type '{}', expected e.g. x4, d8, u4, o8 or f4",
+                spec
+            )
+        };
+        let mut chars = spec.chars();
+        if let Some(size) = spec.strip_prefix('f') {
+            let size = match size {
+                "" => 8,
+                size => size.parse().map_err(|_| bad())?,
+            };
+            if !FLOAT_SIZES.contains(&size) {
+                return Err(format!(
+                    "invalid size {} in type '{}', expected 2, 4 or 8",
+                    size, spec
+                ));
+            }
+            return Ok(Mode::Float(size, float_precision(size)));
+        }
+        let kind = match chars.next() {
+            Some('x') => IntKind::Hex,
+            Some('d') => IntKind::Signed,
+            Some('u') => IntKind::Unsigned,
+            Some('o') => IntKind::Octal,
+            _ => return Err(bad()),
+        };
+        let size = match chars.as_str() {
+            "" | "I" => 4,
+            "C" => 1,
+            "S" => 2,
+            "L" => 8,
+            size => size.parse().map_err(|_| bad())?,
+        };
+        if !GROUP_SIZES.contains(&size) {
+            return Err(format!(
+                "invalid size {} in type '{}', expected 1, 2, 4, 8 or 16",
+                size, spec
+            ));
+        }
+        Ok(Mode::Int(kind, size))
+    }
+}
+
+impl Mode {
+    /// Number of bytes displayed by each conversion of the mode when no
+    /// group size is given
+    pub fn unit_size(&self) -> usize {
+        match self {
+            Mode::OneByteOctal | Mode::OneByteChar | Mode::Canonical | Mode::Binary { .. } => 1,
+            Mode::Default | Mode::TwoBytesDec | Mode::TwoBytesOctal | Mode::TwoBytesHex => 2,
+            Mode::Int(_, size) | Mode::Float(size, _) => *size,
+        }
+    }
+
+    /// Bytes per line when no width is given
+    pub fn default_width(&self) -> usize {
+        match self {
+            Mode::Binary { .. } => DEFAULT_BINARY_WIDTH,
+            _ => DEFAULT_WIDTH,
+        }
+    }
+
+    /// true if the mode displays numbers, which can be grouped
+    pub fn is_numeric(&self) -> bool {
+        !matches!(self, Mode::OneByteChar)
+    }
+
+    /// Number of bytes displayed by each conversion, the width must be a
+    /// multiple of it
+    ///
+    /// Args:
+    ///   group - The --group size, None for the mode's own unit size
+    pub fn group_size(&self, group: Option<usize>) -> usize {
+        match (self, group) {
+            // The size is part of a -t type
+            (Mode::Int(..) | Mode::Float(..), _) => self.unit_size(),
+            (_, Some(group)) if self.is_numeric() => group,
+            _ => self.unit_size(),
+        }
+    }
+
+    /// The format strings of the mode
+    ///
+    /// Args:
+    ///   width - Bytes per line, rounded down to a multiple of the group size
+    ///   group - Bytes shown as one number, one of GROUP_SIZES, None for the
+    ///           mode's own unit size
+    /// Return:
+    ///   The format strings, e.g. for -x with a width of 16
+    ///   `"%07.7_Ax\n"` and `"%07.7_ax " 8/2 "   %04x " "\n"`
+    pub fn format_strings(&self, width: usize, group: Option<usize>) -> Vec<String> {
+        self.layout(width, group, true)
+    }
+
+    /// The format strings of the mode as an extra row under another mode,
+    /// with blanks in place of the address and no end address
+    ///
+    /// Args:
+    ///   width - Bytes per line, rounded down to a multiple of the group size
+    ///   group - Bytes shown as one number, one of GROUP_SIZES, None for the
+    ///           mode's own unit size
+    /// Return:
+    ///   The format strings, e.g. for -c with a width of 16
+    ///   `"        " 16/1 "%3_c " "\n"`
+    pub fn row_format_strings(&self, width: usize, group: Option<usize>) -> Vec<String> {
+        self.layout(width, group, false)
+    }
+
+    /// The format strings of the mode, with or without the addresses
+    fn layout(&self, width: usize, group: Option<usize>, address: bool) -> Vec<String> {
+        let size = self.group_size(group);
+        let units = (width / size).max(1);
+        // The address, or blanks as wide as it
+        let (start, end) = match (self, address) {
+            (Mode::Canonical, true) => ("%08.8_ax", Some(r#""%08.8_Ax\n""#)),
+            (Mode::Canonical, false) => ("        ", None),
+            (_, true) => ("%07.7_ax", Some(r#""%07.7_Ax\n""#)),
+            (_, false) => ("       ", None),
+        };
+        let line = |conv: String| format!(r#""{} " {}/{} "{}" "\n""#, start, units, size, conv);
+        let strings = match self {
+            Mode::Default => vec![line(format!("%0{}x ", 2 * size))],
+            Mode::OneByteOctal => vec![line(format!("%0{}o ", max_digits(size, 8)))],
+            Mode::OneByteChar => vec![line("%3_c ".to_string())],
+            Mode::TwoBytesDec => vec![line(format!("  %0{}u ", max_digits(size, 10)))],
+            Mode::TwoBytesOctal => vec![line(format!(" %0{}o ", max_digits(size, 8)))],
+            Mode::TwoBytesHex => vec![line(format!("   %0{}x ", 2 * size))],
+            // Like od, every value has a space before it and none after
+            Mode::Int(kind, _) => {
+                let conv = match kind {
+                    IntKind::Hex => format!(" %0{}x", 2 * size),
+                    IntKind::Signed => format!(" %{}d", max_signed_len(size)),
+                    IntKind::Unsigned => format!(" %{}u", max_digits(size, 10)),
+                    IntKind::Octal => format!(" %0{}o", max_digits(size, 8)),
+                };
+                vec![format!(r#""{}" {}/{} "{}" "\n""#, start, units, size, conv)]
+            }
+            // Room for a sign, the point and an exponent of up to 2 digits, or
+            // 3 for doubles
+            Mode::Float(_, precision) => {
+                let precision = (*precision).max(1);
+                let exponent = if size == 8 { 3 } else { 2 };
+                let conv = format!(" %{}.{}g", precision + 4 + exponent, precision);
+                vec![format!(r#""{}" {}/{} "{}" "\n""#, start, units, size, conv)]
+            }
+            Mode::Binary { nibbles, ascii } => {
+                // The width pads the missing units of the last line
+                let conv = if *nibbles {
+                    format!(" %{}_b", 10 * size - 1)
+                } else {
+                    format!(" %0{}b", 8 * size)
+                };
+                let bits = format!(r#""{}" {}/{} "{}""#, start, units, size, conv);
+                if *ascii {
+                    vec![bits, format!(r#""  |" {}/1 "%_p" "|\n""#, units * size)]
+                } else {
+                    vec![bits + r#" "\n""#]
+                }
+            }
+            Mode::Canonical => {
+                // The extra space in the middle of the line moves with the width
+                let first = units / 2;
+                let second = units - first;
+                // A unit that isn't repeated keeps its trailing space
+                let half = |n: usize| match n {
+                    1 => format!(r#" 1/{} "%0{}x""#, size, 2 * size),
+                    n => format!(r#" {}/{} "%0{}x ""#, n, size, 2 * size),
+                };
+                let mut hex = format!(r#""{}  ""#, start);
+                if first > 0 {
+                    hex.push_str(&half(first));
+                    hex.push_str(r#" "  ""#);
+                }
+                hex.push_str(&half(second));
+                vec![hex, format!(r#""  |" {}/1 "%_p" "|\n""#, units * size)]
+            }
+        };
+        end.map(String::from).into_iter().chain(strings).collect()
+    }
+}
+
+/// Names printed by %_u for the control characters 0x00 - 0x1f
+const UNIT_NAMES: [&str; 32] = [
+    "nul", "soh", "stx", "etx", "eot", "enq", "ack", "bel", "bs", "ht", "lf", "vt", "ff", "cr",
+    "so", "si", "dle", "dc1", "dc2", "dc3", "dc4", "nak", "syn", "etb", "can", "em", "sub", "esc",
+    "fs", "gs", "rs", "us",
+];
+
+/// An error found while parsing a format string
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatError {
+    /// The format file and 1-based line the format string was read from
+    pub file: Option<(String, usize)>,
+    /// 1-based column in the format string where the error was found
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some((name, line)) => write!(f, "{}:{}:{}: {}", name, line, self.column, self.message),
+            None => write!(f, "column {}: {}", self.column, self.message),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Radix used by the %_a and %_A address conversions
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Radix {
+    Dec,
+    Oct,
+    Hex,
+}
+
+/// What a single conversion prints
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConvKind {
+    /// %_a, the address of the next byte
+    Address(Radix),
+    /// %_A, the address after all input has been processed
+    EndAddress(Radix),
+    /// %c, the raw byte
+    Char,
+    /// %_c, the byte as a character with C escapes or octal
+    EscChar,
+    /// %_p, the byte if printable or '.'
+    Printable,
+    /// %_u, the byte with US ASCII names for control characters
+    UnitName,
+    /// %d and %i
+    Signed,
+    /// %u
+    Unsigned,
+    /// %o
+    Octal,
+    /// %x and %X
+    Hex { upper: bool },
+    /// %b, binary like C23, and %_b, every bit of the unit with a space
+    /// between nibbles
+    Binary { nibbles: bool },
+    /// %e, %f and %g and their upper case forms
+    Float { style: FloatStyle, upper: bool },
+    /// %s
+    Str,
+}
+
+/// The printf style of a floating point conversion
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FloatStyle {
+    /// %e, d.ddde+dd
+    Exp,
+    /// %f, ddd.ddd
+    Fixed,
+    /// %g, the shorter of %e and %f without trailing zeros
+    General,
+}
+
+/// The printf flags, width and precision of a conversion
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Spec {
+    left: bool,
+    plus: bool,
+    space: bool,
+    alt: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Conversion {
+    spec: Spec,
+    kind: ConvKind,
+    /// Number of input bytes consumed by this conversion
+    byte_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Text(String),
+    Conv(Conversion),
+}
+
+/// One `iterations/byte_count "format"` unit
+#[derive(Debug, Clone, PartialEq)]
+struct Unit {
+    iterations: usize,
+    explicit_iterations: bool,
+    pieces: Vec<Piece>,
+}
+
+impl Unit {
+    /// Number of input bytes consumed by one iteration of the unit
+    fn bytes(&self) -> usize {
+        self.pieces
+            .iter()
+            .map(|p| match p {
+                Piece::Conv(c) => c.byte_count,
+                Piece::Text(_) => 0,
+            })
+            .sum()
+    }
+
+    /// true if the unit contains a %_A conversion
+    fn has_end_address(&self) -> bool {
+        self.pieces
+            .iter()
+            .any(|p| matches!(p, Piece::Conv(c) if matches!(c.kind, ConvKind::EndAddress(_))))
+    }
+}
+
+/// A single parsed format string, as given to one -e option
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatString {
+    units: Vec<Unit>,
+}
+
+impl FormatString {
+    /// Number of input bytes consumed by the whole format string
+    fn bytes(&self) -> usize {
+        self.units.iter().map(|u| u.iterations * u.bytes()).sum()
+    }
+}
+
+/// Byte order used to read multi-byte values
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Endian {
+    /// Least significant byte first, what hexdump(1) does on x86
+    #[default]
+    Little,
+    /// Most significant byte first, network byte order
+    Big,
+}
+
+impl Endian {
+    /// The byte order of the machine hd is running on
+    pub fn native() -> Endian {
+        if cfg!(target_endian = "big") {
+            Endian::Big
+        } else {
+            Endian::Little
+        }
+    }
+}
+
+/// A set of format strings ready to display blocks of input
+#[derive(Debug, Clone, PartialEq)]
+pub struct Format {
+    strings: Vec<FormatString>,
+    block_size: usize,
+    endian: Endian,
+    color: bool,
+}
+
+/// Parse a number starting at bytes[*i], advancing i past it
+fn parse_number(bytes: &[u8], i: &mut usize) -> Option<usize> {
+    let start = *i;
+    let mut value: usize = 0;
+    while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+        value = value
+            .checked_mul(10)?
+            .checked_add((bytes[*i] - b'0') as usize)?;
+        *i += 1;
+    }
+    if *i == start { None } else { Some(value) }
+}
+
+/// Skip over whitespace starting at bytes[*i]
+fn skip_whitespace(bytes: &[u8], i: &mut usize) {
+    while *i < bytes.len() && bytes[*i].is_ascii_whitespace() {
+        *i += 1;
+    }
+}
+
+/// Build a FormatError pointing at byte offset `at` in `s`
+fn error_at(s: &str, at: usize, message: String) -> FormatError {
+    let column = s
+        .get(..at)
+        .map(|prefix| prefix.chars().count())
+        .unwrap_or(at)
+        + 1;
+    FormatError {
+        file: None,
+        column,
+        message,
+    }
+}
+
+/// Parse a single format string made up of one or more format units
+///
+/// Args:
+///   s - The format string, e.g. `"%07.7_ax " 8/2 "%04x " "\n"`
+/// Return:
+///   The parsed FormatString or a FormatError with the column of the problem
+pub fn parse_format_string(s: &str) -> Result<FormatString, FormatError> {
+    let bytes = s.as_bytes();
+    let mut units = Vec::new();
+    let mut i = 0;
+    loop {
+        skip_whitespace(bytes, &mut i);
+        if i == bytes.len() {
+            break;
+        }
+        let unit_start = i;
+        let mut iterations = 1;
+        let mut explicit_iterations = false;
+        if bytes[i].is_ascii_digit() {
+            iterations = parse_number(bytes, &mut i)
+                .ok_or_else(|| error_at(s, unit_start, "iteration count is too large".into()))?;
+            if iterations == 0 {
+                return Err(error_at(
+                    s,
+                    unit_start,
+                    "iteration count must not be 0".into(),
+                ));
+            }
+            explicit_iterations = true;
+            skip_whitespace(bytes, &mut i);
+        }
+        let mut byte_count = None;
+        if i < bytes.len() && bytes[i] == b'/' {
+            i += 1;
+            skip_whitespace(bytes, &mut i);
+            let count_start = i;
+            let count = parse_number(bytes, &mut i).ok_or_else(|| {
+                error_at(s, count_start, "expected a byte count after '/'".into())
+            })?;
+            if count == 0 {
+                return Err(error_at(s, count_start, "byte count must not be 0".into()));
+            }
+            byte_count = Some(count);
+            skip_whitespace(bytes, &mut i);
+        }
+        if i == bytes.len() || bytes[i] != b'"' {
+            return Err(error_at(s, i, "expected a quoted format".into()));
+        }
+        i += 1;
+        let pieces = parse_quoted(s, &mut i, byte_count)?;
+        units.push(Unit {
+            iterations,
+            explicit_iterations,
+            pieces,
+        });
+    }
+    Ok(FormatString { units })
+}
+
+/// Parse a list of format strings, as given to several -e options
+pub fn parse_format_strings<S: AsRef<str>>(
+    strings: &[S],
+) -> Result<Vec<FormatString>, FormatError> {
+    strings
+        .iter()
+        .map(|s| parse_format_string(s.as_ref()))
+        .collect()
+}
+
+/// Parse the contents of a format file, as given to -f
+///
+/// Each line holds one format string.  Blank lines and lines whose first
+/// non-blank character is '#' are ignored.
+///
+/// Args:
+///   name - The name of the format file, used in error messages
+///   contents - The text of the format file
+/// Return:
+///   The parsed format strings or a FormatError with the file, line and column
+pub fn parse_format_file(name: &str, contents: &str) -> Result<Vec<FormatString>, FormatError> {
+    let mut strings = Vec::new();
+    for (n, line) in contents.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let string = parse_format_string(line).map_err(|mut err| {
+            err.file = Some((name.to_string(), n + 1));
+            err
+        })?;
+        strings.push(string);
+    }
+    Ok(strings)
+}
+
+/// Parse the inside of a quoted format, up to and including the closing quote
+fn parse_quoted(
+    s: &str,
+    i: &mut usize,
+    byte_count: Option<usize>,
+) -> Result<Vec<Piece>, FormatError> {
+    let bytes = s.as_bytes();
+    let open = *i - 1;
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut data_conversions = 0;
+    loop {
+        let Some(c) = s[*i..].chars().next() else {
+            return Err(error_at(s, open, "unterminated quoted format".into()));
+        };
+        match c {
+            '"' => {
+                *i += 1;
+                break;
+            }
+            '\\' => {
+                *i += 1;
+                let Some(e) = s[*i..].chars().next() else {
+                    return Err(error_at(s, open, "unterminated quoted format".into()));
+                };
+                text.push(match e {
+                    '0' => '\0',
+                    'a' => '\x07',
+                    'b' => '\x08',
+                    'f' => '\x0c',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'v' => '\x0b',
+                    other => other,
+                });
+                *i += e.len_utf8();
+            }
+            '%' if bytes.get(*i + 1) == Some(&b'%') => {
+                text.push('%');
+                *i += 2;
+            }
+            '%' => {
+                let conv_start = *i;
+                let mut conv = parse_conversion(s, i)?;
+                match conv.kind {
+                    ConvKind::Address(_) | ConvKind::EndAddress(_) => conv.byte_count = 0,
+                    _ => {
+                        data_conversions += 1;
+                        if data_conversions > 1 && byte_count.is_some() {
+                            return Err(error_at(
+                                s,
+                                conv_start,
+                                "a byte count may only be used with a single conversion".into(),
+                            ));
+                        }
+                        conv.byte_count = conversion_bytes(s, conv_start, &conv, byte_count)?;
+                    }
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Conv(conv));
+            }
+            other => {
+                text.push(other);
+                *i += other.len_utf8();
+            }
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+/// Parse a single %conversion starting at s[*i]
+fn parse_conversion(s: &str, i: &mut usize) -> Result<Conversion, FormatError> {
+    let bytes = s.as_bytes();
+    let start = *i;
+    *i += 1;
+    let mut spec = Spec::default();
+    while *i < bytes.len() {
+        match bytes[*i] {
+            b'-' => spec.left = true,
+            b'+' => spec.plus = true,
+            b' ' => spec.space = true,
+            b'#' => spec.alt = true,
+            b'0' => spec.zero = true,
+            _ => break,
+        }
+        *i += 1;
+    }
+    if let Some(width) = parse_number(bytes, i) {
+        spec.width = width;
+    }
+    if *i < bytes.len() && bytes[*i] == b'.' {
+        *i += 1;
+        spec.precision = Some(parse_number(bytes, i).unwrap_or(0));
+    }
+    let bad = |i: usize| {
+        let end = (i + 1).min(s.len());
+        error_at(
+            s,
+            start,
+            format!(
+                "bad conversion character {}",
+                s.get(start..end).unwrap_or("%")
+            ),
+        )
+    };
+    let kind = match bytes.get(*i) {
+        Some(b'c') => ConvKind::Char,
+        Some(b'd') | Some(b'i') => ConvKind::Signed,
+        Some(b'u') => ConvKind::Unsigned,
+        Some(b'o') => ConvKind::Octal,
+        Some(b'x') => ConvKind::Hex { upper: false },
+        Some(b'X') => ConvKind::Hex { upper: true },
+        Some(b'b') => ConvKind::Binary { nibbles: false },
+        Some(&c @ (b'e' | b'E' | b'f' | b'F' | b'g' | b'G')) => ConvKind::Float {
+            style: match c.to_ascii_lowercase() {
+                b'e' => FloatStyle::Exp,
+                b'f' => FloatStyle::Fixed,
+                _ => FloatStyle::General,
+            },
+            upper: c.is_ascii_uppercase(),
+        },
+        Some(b's') => ConvKind::Str,
+        Some(b'_') => {
+            *i += 1;
+            match bytes.get(*i) {
+                Some(b'c') => ConvKind::EscChar,
+                Some(b'p') => ConvKind::Printable,
+                Some(b'u') => ConvKind::UnitName,
+                Some(b'b') => ConvKind::Binary { nibbles: true },
+                Some(&c @ b'a') | Some(&c @ b'A') => {
+                    *i += 1;
+                    let radix = match bytes.get(*i) {
+                        Some(b'd') => Radix::Dec,
+                        Some(b'o') => Radix::Oct,
+                        Some(b'x') => Radix::Hex,
+                        _ => return Err(bad(*i)),
+                    };
+                    if c == b'a' {
+                        ConvKind::Address(radix)
+                    } else {
+                        ConvKind::EndAddress(radix)
+                    }
+                }
+                _ => return Err(bad(*i)),
+            }
+        }
+        _ => return Err(bad(*i)),
+    };
+    *i += 1;
+    Ok(Conversion {
+        spec,
+        kind,
+        byte_count: 0,
+    })
+}
+
+/// Work out and validate how many bytes a conversion consumes
+fn conversion_bytes(
+    s: &str,
+    at: usize,
+    conv: &Conversion,
+    byte_count: Option<usize>,
+) -> Result<usize, FormatError> {
+    let bad_count = |count: usize| {
+        Err(error_at(
+            s,
+            at,
+            format!("byte count {} is not valid for this conversion", count),
+        ))
+    };
+    match conv.kind {
+        ConvKind::Char | ConvKind::EscChar | ConvKind::Printable | ConvKind::UnitName => {
+            match byte_count {
+                None | Some(1) => Ok(1),
+                Some(count) => bad_count(count),
+            }
+        }
+        ConvKind::Signed
+        | ConvKind::Unsigned
+        | ConvKind::Octal
+        | ConvKind::Hex { .. }
+        | ConvKind::Binary { .. } => match byte_count {
+            // %_b works on bytes like the other %_ conversions
+            None if matches!(conv.kind, ConvKind::Binary { nibbles: true }) => Ok(1),
+            None => Ok(4),
+            Some(count @ (1 | 2 | 4 | 8 | 16)) => Ok(count),
+            Some(count) => bad_count(count),
+        },
+        // Half, single and double precision
+        ConvKind::Float { .. } => match byte_count {
+            None => Ok(8),
+            Some(count @ (2 | 4 | 8)) => Ok(count),
+            Some(count) => bad_count(count),
+        },
+        ConvKind::Str => match byte_count.or(conv.spec.precision) {
+            Some(count) if count > 0 => Ok(count),
+            _ => Err(error_at(
+                s,
+                at,
+                "%s needs a byte count or a precision".into(),
+            )),
+        },
+        ConvKind::Address(_) | ConvKind::EndAddress(_) => Ok(0),
+    }
+}
+
+/// Digits used for every radix, lower case
+const DIGITS_LOWER: &[u8; 16] = b"0123456789abcdef";
+
+/// Digits used for every radix, upper case
+const DIGITS_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Big enough for a u128 in any radix from 2 up
+const DIGITS_LEN: usize = 128;
+
+/// Write the digits of value into the end of buf
+///
+/// Args:
+///   buf - Scratch space for the digits
+///   value - The value to convert
+///   radix - 2, 8, 10 or 16
+///   upper - Use upper case digits above 9
+/// Return:
+///   The digits, a slice of the end of buf
+fn to_digits(buf: &mut [u8; DIGITS_LEN], value: u128, radix: u64, upper: bool) -> &[u8] {
+    let table = if upper { DIGITS_UPPER } else { DIGITS_LOWER };
+    let mut start = DIGITS_LEN;
+    // 128-bit division is slow, only use it for the top of 16-byte values
+    let mut high = value;
+    let mut low = loop {
+        if let Ok(low) = u64::try_from(high) {
+            break low;
+        }
+        start -= 1;
+        buf[start] = table[(high % radix as u128) as usize];
+        high /= radix as u128;
+    };
+    loop {
+        start -= 1;
+        buf[start] = table[(low % radix) as usize];
+        low /= radix;
+        if low == 0 {
+            break;
+        }
+    }
+    &buf[start..]
+}
+
+/// Read an unsigned value of size bytes
+///
+/// Args:
+///   bytes - The bytes of the value, shorter than size at the end of input
+///           in which case the missing bytes are zero
+///   size - The size of the value in bytes
+///   endian - The byte order of the value
+fn unsigned_value(bytes: &[u8], size: usize, endian: Endian) -> u128 {
+    let fold = |acc: u128, b: &u8| (acc << 8) | *b as u128;
+    match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold) << (8 * (size - bytes.len())),
+    }
+}
+
+/// Read a signed value of size bytes, sign extending it
+///
+/// Args:
+///   bytes - The bytes of the value, shorter than size at the end of input
+///   size - The size of the value in bytes
+///   endian - The byte order of the value
+fn signed_value(bytes: &[u8], size: usize, endian: Endian) -> i128 {
+    let value = unsigned_value(bytes, size, endian);
+    let shift = 128 - 8 * size as u32;
+    ((value << shift) as i128) >> shift
+}
+
+/// Append `body` to out, padded to the width of spec
+fn pad(out: &mut Vec<u8>, spec: &Spec, body: &[u8]) {
+    let fill = spec.width.saturating_sub(body.len());
+    if !spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+    out.extend_from_slice(body);
+    if spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+}
+
+/// Append an integer to out following the printf rules for spec
+///
+/// Args:
+///   out - The output buffer
+///   spec - The flags, width and precision of the conversion
+///   sign - The sign to print, if any
+///   prefix - The alternate form prefix, e.g. "0x"
+///   digits - The digits of the magnitude of the value
+fn pad_number(out: &mut Vec<u8>, spec: &Spec, sign: &[u8], prefix: &[u8], digits: &[u8]) {
+    let mut digits = digits;
+    let mut zeros = 0;
+    if let Some(precision) = spec.precision {
+        if precision == 0 && digits == b"0" {
+            digits = b"";
+        }
+        zeros = precision.saturating_sub(digits.len());
+    }
+    let len = sign.len() + prefix.len() + zeros + digits.len();
+    let fill = spec.width.saturating_sub(len);
+    if spec.zero && !spec.left && spec.precision.is_none() {
+        zeros += fill;
+    } else if !spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+    out.extend_from_slice(sign);
+    out.extend_from_slice(prefix);
+    out.resize(out.len() + zeros, b'0');
+    out.extend_from_slice(digits);
+    if spec.left {
+        out.resize(out.len() + fill, b' ');
+    }
+}
+
+/// Append an unsigned value in the given radix
+fn write_unsigned(out: &mut Vec<u8>, spec: &Spec, kind: ConvKind, value: u128) {
+    let mut buf = [0u8; DIGITS_LEN];
+    match kind {
+        ConvKind::Octal => {
+            let prefix: &[u8] = if spec.alt && value != 0 { b"0" } else { b"" };
+            pad_number(out, spec, b"", prefix, to_digits(&mut buf, value, 8, false));
+        }
+        ConvKind::Hex { upper } => {
+            let prefix: &[u8] = match (spec.alt && value != 0, upper) {
+                (false, _) => b"",
+                (true, true) => b"0X",
+                (true, false) => b"0x",
+            };
+            pad_number(
+                out,
+                spec,
+                b"",
+                prefix,
+                to_digits(&mut buf, value, 16, upper),
+            );
+        }
+        ConvKind::Binary { .. } => {
+            let prefix: &[u8] = if spec.alt && value != 0 { b"0b" } else { b"" };
+            pad_number(out, spec, b"", prefix, to_digits(&mut buf, value, 2, false));
+        }
+        _ => pad_number(out, spec, b"", b"", to_digits(&mut buf, value, 10, false)),
+    }
+}
+
+/// Append every bit of a value of size bytes, most significant first, with a
+/// space between nibbles
+fn write_nibbles(out: &mut Vec<u8>, spec: &Spec, value: u128, size: usize) {
+    // 128 bits and a space after all but the last of their 32 nibbles
+    let mut buf = [0u8; DIGITS_LEN + DIGITS_LEN / 4];
+    let mut len = 0;
+    for bit in (0..8 * size).rev() {
+        buf[len] = b'0' + ((value >> bit) & 1) as u8;
+        len += 1;
+        if bit % 4 == 0 && bit > 0 {
+            buf[len] = b' ';
+            len += 1;
+        }
+    }
+    pad(out, spec, &buf[..len]);
+}
+
+/// Append a signed decimal value
+fn write_signed(out: &mut Vec<u8>, spec: &Spec, value: i128) {
+    let sign: &[u8] = if value < 0 {
+        b"-"
+    } else if spec.plus {
+        b"+"
+    } else if spec.space {
+        b" "
+    } else {
+        b""
+    };
+    let mut buf = [0u8; DIGITS_LEN];
+    let digits = to_digits(&mut buf, value.unsigned_abs(), 10, false);
+    pad_number(out, spec, sign, b"", digits);
+}
+
+/// Convert the bits of an IEEE-754 half precision value
+fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let fraction = (bits & 0x3ff) as f64;
+    match exponent {
+        // Zero and subnormals
+        0 => sign * fraction * 2f64.powi(-24),
+        0x1f if fraction == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN.copysign(sign),
+        _ => sign * (1.0 + fraction / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Read a floating point value of size bytes
+///
+/// Args:
+///   bytes - The bytes of the value, shorter than size at the end of input
+///   size - 2, 4 or 8 for half, single or double precision
+///   endian - The byte order of the value
+fn float_value(bytes: &[u8], size: usize, endian: Endian) -> f64 {
+    let bits = unsigned_value(bytes, size, endian);
+    match size {
+        2 => f16_to_f64(bits as u16),
+        4 => f32::from_bits(bits as u32) as f64,
+        _ => f64::from_bits(bits as u64),
+    }
+}
+
+/// Format the magnitude of a finite value like printf %e
+fn format_exp(value: f64, precision: usize) -> String {
+    let text = format!("{:.*e}", precision, value);
+    let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let sign = if exponent < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exponent.abs())
+}
+
+/// Remove the trailing zeros of the fraction of a %g value
+fn trim_fraction(text: &mut String) {
+    let end = text.find('e').unwrap_or(text.len());
+    if !text[..end].contains('.') {
+        return;
+    }
+    let exponent = text.split_off(end);
+    let trimmed = text.trim_end_matches('0').trim_end_matches('.').len();
+    text.truncate(trimmed);
+    text.push_str(&exponent);
+}
+
+/// Append a floating point value following the printf rules for spec
+fn write_float(out: &mut Vec<u8>, spec: &Spec, style: FloatStyle, upper: bool, value: f64) {
+    let sign: &[u8] = if value.is_sign_negative() {
+        b"-"
+    } else if spec.plus {
+        b"+"
+    } else if spec.space {
+        b" "
+    } else {
+        b""
+    };
+    let precision = spec.precision.unwrap_or(6);
+    let magnitude = value.abs();
+    let mut text = if magnitude.is_nan() {
+        "nan".to_string()
+    } else if magnitude.is_infinite() {
+        "inf".to_string()
+    } else {
+        match style {
+            FloatStyle::Exp => format_exp(magnitude, precision),
+            FloatStyle::Fixed => format!("{:.*}", precision, magnitude),
+            FloatStyle::General => {
+                let precision = precision.max(1);
+                let exp_text = format_exp(magnitude, precision - 1);
+                let exponent: i32 = exp_text[exp_text.find('e').unwrap_or(0) + 1..]
+                    .parse()
+                    .unwrap_or(0);
+                let mut text = if exponent < -4 || exponent >= precision as i32 {
+                    exp_text
+                } else {
+                    format!(
+                        "{:.*}",
+                        (precision as i32 - 1 - exponent) as usize,
+                        magnitude
+                    )
+                };
+                if !spec.alt {
+                    trim_fraction(&mut text);
+                }
+                text
+            }
+        }
+    };
+    if spec.alt && !text.contains('.') && magnitude.is_finite() {
+        let at = text.find('e').unwrap_or(text.len());
+        text.insert(at, '.');
+    }
+    if upper {
+        text.make_ascii_uppercase();
+    }
+    // The precision has been applied, and inf and nan are never zero padded
+    let spec = Spec {
+        precision: None,
+        zero: spec.zero && magnitude.is_finite(),
+        ..spec.clone()
+    };
+    pad_number(out, &spec, sign, b"", text.as_bytes());
+}
+
+/// Append the %_c representation of a byte
+fn write_esc_char(out: &mut Vec<u8>, spec: &Spec, byte: u8) {
+    let escaped = match byte {
+        0 => "\\0",
+        7 => "\\a",
+        8 => "\\b",
+        9 => "\\t",
+        10 => "\\n",
+        11 => "\\v",
+        12 => "\\f",
+        13 => "\\r",
+        0x20..=0x7e => return pad(out, spec, &[byte]),
+        _ => {
+            let octal = [
+                DIGITS_LOWER[(byte >> 6) as usize],
+                DIGITS_LOWER[((byte >> 3) & 7) as usize],
+                DIGITS_LOWER[(byte & 7) as usize],
+            ];
+            return pad(out, spec, &octal);
+        }
+    };
+    pad(out, spec, escaped.as_bytes());
+}
+
+/// Append the %_u representation of a byte
+fn write_unit_name(out: &mut Vec<u8>, spec: &Spec, byte: u8) {
+    match byte {
+        0..=0x1f => pad(out, spec, UNIT_NAMES[byte as usize].as_bytes()),
+        0x7f => pad(out, spec, b"del"),
+        0x20..=0x7e => pad(out, spec, &[byte]),
+        _ => write_unsigned(out, spec, ConvKind::Hex { upper: false }, byte as u128),
+    }
+}
+
+/// Append an address in the radix of an %_a or %_A conversion
+fn write_address(out: &mut Vec<u8>, spec: &Spec, radix: Radix, address: usize) {
+    let kind = match radix {
+        Radix::Dec => ConvKind::Unsigned,
+        Radix::Oct => ConvKind::Octal,
+        Radix::Hex => ConvKind::Hex { upper: false },
+    };
+    write_unsigned(out, spec, kind, address as u128);
+}
+
+impl Conversion {
+    /// Append this conversion applied to bytes to out
+    ///
+    /// bytes is byte_count long, or shorter when the conversion runs past the
+    /// end of the input in which case the missing bytes read as zeros.
+    /// Multi-byte values are read in the endian byte order.
+    fn write(&self, out: &mut Vec<u8>, bytes: &[u8], endian: Endian) {
+        let size = self.byte_count;
+        let spec = &self.spec;
+        match self.kind {
+            ConvKind::Char => pad(out, spec, &bytes[..1]),
+            ConvKind::EscChar => write_esc_char(out, spec, bytes[0]),
+            ConvKind::Printable => pad(out, spec, &[crate::printable_byte(bytes[0])]),
+            ConvKind::UnitName => write_unit_name(out, spec, bytes[0]),
+            ConvKind::Signed => write_signed(out, spec, signed_value(bytes, size, endian)),
+            ConvKind::Unsigned
+            | ConvKind::Octal
+            | ConvKind::Hex { .. }
+            | ConvKind::Binary { nibbles: false } => {
+                write_unsigned(out, spec, self.kind, unsigned_value(bytes, size, endian))
+            }
+            ConvKind::Binary { nibbles: true } => {
+                write_nibbles(out, spec, unsigned_value(bytes, size, endian), size)
+            }
+            ConvKind::Float { style, upper } => {
+                write_float(out, spec, style, upper, float_value(bytes, size, endian))
+            }
+            ConvKind::Str => {
+                let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+                let end = spec.precision.map_or(end, |p| end.min(p));
+                pad(out, spec, &bytes[..end]);
+            }
+            ConvKind::Address(_) | ConvKind::EndAddress(_) => {}
+        }
+    }
+}
+
+impl Format {
+    /// Build a Format out of parsed format strings
+    ///
+    /// Works out the block size and, like hexdump(1), repeats the last unit
+    /// of any format string that has no explicit iteration count so that it
+    /// fills the block.
+    pub fn new(mut strings: Vec<FormatString>) -> Format {
+        let block_size = strings.iter().map(|s| s.bytes()).max().unwrap_or(0).max(1);
+        for string in strings.iter_mut() {
+            let bytes = string.bytes();
+            if let Some(last) = string.units.last_mut() {
+                let unit_bytes = last.bytes();
+                if bytes > 0 && bytes < block_size && !last.explicit_iterations && unit_bytes > 0 {
+                    last.iterations += (block_size - bytes) / unit_bytes;
+                }
+            }
+        }
+        Format {
+            strings,
+            block_size,
+            endian: Endian::Little,
+            color: false,
+        }
+    }
+
+    /// Set the byte order multi-byte conversions read values in
+    ///
+    /// Args:
+    ///   endian - The byte order, little-endian by default
+    /// Return:
+    ///   The Format with the new byte order
+    pub fn with_endian(mut self, endian: Endian) -> Format {
+        self.endian = endian;
+        self
+    }
+
+    /// Color the conversions that display input by the class of their bytes
+    ///
+    /// Args:
+    ///   color - true to add ANSI colors, off by default
+    /// Return:
+    ///   The Format with colors on or off
+    pub fn with_color(mut self, color: bool) -> Format {
+        self.color = color;
+        self
+    }
+
+    /// Number of input bytes displayed per block
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// true if any format string displays input bytes
+    ///
+    /// Without one the block size is clamped to 1 and each block only shows
+    /// an address, so repeated blocks mustn't be squeezed.
+    pub fn displays_input(&self) -> bool {
+        self.strings.iter().any(|s| s.bytes() > 0)
+    }
+
+    /// Display one block of input
+    ///
+    /// Args:
+    ///   out - Buffer the formatted text is appended to
+    ///   block - The input, shorter than block_size only for the final block
+    ///   address - The address of the first byte of block
+    pub fn display(&self, out: &mut Vec<u8>, block: &[u8], address: usize) {
+        self.display_marked(out, block, address, &[]);
+    }
+
+    /// Display one block of input with some of its bytes highlighted
+    ///
+    /// Args:
+    ///   out - Buffer the formatted text is appended to
+    ///   block - The input, shorter than block_size only for the final block
+    ///   address - The address of the first byte of block
+    ///   marked - true for the bytes of block to highlight, the conversions
+    ///            that display any of them are shown in reverse video.  Empty
+    ///            for no highlighting
+    pub fn display_marked(&self, out: &mut Vec<u8>, block: &[u8], address: usize, marked: &[bool]) {
+        self.write_block(out, block, address, marked, None);
+    }
+
+    /// Display one block of input with a line of carets under the marked bytes
+    ///
+    /// Each line of the block that displays a marked byte is followed by a
+    /// line with ^ under every character of the conversions that display it,
+    /// for when colors are off.
+    ///
+    /// Args:
+    ///   out - Buffer the formatted text is appended to
+    ///   block - The input, shorter than block_size only for the final block
+    ///   address - The address of the first byte of block
+    ///   marked - true for the bytes of block to mark
+    pub fn display_carets(&self, out: &mut Vec<u8>, block: &[u8], address: usize, marked: &[bool]) {
+        let mut text = Vec::new();
+        let mut carets = Vec::new();
+        self.write_block(&mut text, block, address, marked, Some(&mut carets));
+        // carets has the same lines as text, with the same columns
+        let lines = text.split_inclusive(|c| *c == b'\n');
+        for (line, caret_line) in lines.zip(carets.split_inclusive(|c| *c == b'\n')) {
+            out.extend_from_slice(line);
+            if !caret_line.contains(&b'^') {
+                continue;
+            }
+            if !line.ends_with(b"\n") {
+                out.push(b'\n');
+            }
+            let len = caret_line
+                .iter()
+                .rposition(|c| *c == b'^')
+                .map_or(0, |last| last + 1);
+            out.extend_from_slice(&caret_line[..len]);
+            out.push(b'\n');
+        }
+    }
+
+    /// Display one block of input
+    ///
+    /// Args:
+    ///   out - Buffer the formatted text is appended to
+    ///   block - The input, shorter than block_size only for the final block
+    ///   address - The address of the first byte of block
+    ///   marked - true for the bytes of block to mark
+    ///   carets - None to highlight the marked bytes in reverse video, or a
+    ///            buffer that gets a copy of out with ^ for the characters
+    ///            that display marked bytes and spaces for the rest
+    fn write_block(
+        &self,
+        out: &mut Vec<u8>,
+        block: &[u8],
+        address: usize,
+        marked: &[bool],
+        mut carets: Option<&mut Vec<u8>>,
+    ) {
+        // Copy text, as it is written to out, to carets
+        let shadow = |carets: &mut Option<&mut Vec<u8>>, text: &[u8], mark: bool| {
+            if let Some(carets) = carets {
+                for c in text {
+                    match c {
+                        b'\n' | b'\t' => carets.push(*c),
+                        // One column for each UTF-8 sequence
+                        0x80..=0xbf => {}
+                        _ if mark => carets.push(b'^'),
+                        _ => carets.push(b' '),
+                    }
+                }
+            }
+        };
+        let mut conv_text = Vec::new();
+        for string in &self.strings {
+            let mut pos = 0;
+            for unit in &string.units {
+                if unit.has_end_address() {
+                    break;
+                }
+                for rep in 0..unit.iterations {
+                    let last_rep = unit.iterations > 1 && rep + 1 == unit.iterations;
+                    for (n, piece) in unit.pieces.iter().enumerate() {
+                        match piece {
+                            Piece::Text(text) => {
+                                let mut text = text.as_bytes();
+                                // hexdump(1) drops the trailing whitespace of the
+                                // final iteration of a repeated unit
+                                if last_rep && n + 1 == unit.pieces.len() {
+                                    if let Some((last, rest)) = text.split_last() {
+                                        if last.is_ascii_whitespace() || *last == b'\x0b' {
+                                            text = rest;
+                                        }
+                                    }
+                                }
+                                out.extend_from_slice(text);
+                                shadow(&mut carets, text, false);
+                            }
+                            Piece::Conv(conv) => {
+                                let start = out.len();
+                                if pos >= block.len() {
+                                    pad(out, &conv.spec, b"");
+                                    shadow(&mut carets, &out[start..], false);
+                                } else if let ConvKind::Address(radix) = conv.kind {
+                                    write_address(out, &conv.spec, radix, address + pos);
+                                    shadow(&mut carets, &out[start..], false);
+                                } else {
+                                    let end = block.len().min(pos + conv.byte_count);
+                                    let bytes = &block[pos..end];
+                                    let mark = marked[pos.min(marked.len())..end.min(marked.len())]
+                                        .contains(&true);
+                                    conv_text.clear();
+                                    conv.write(&mut conv_text, bytes, self.endian);
+                                    shadow(&mut carets, &conv_text, mark);
+                                    let color = if mark && carets.is_none() {
+                                        Some(color::MARK)
+                                    } else if self.color {
+                                        color::unit_color(bytes)
+                                    } else {
+                                        None
+                                    };
+                                    match color {
+                                        Some(color) => {
+                                            out.extend_from_slice(color.as_bytes());
+                                            out.extend_from_slice(&conv_text);
+                                            out.extend_from_slice(color::RESET.as_bytes());
+                                        }
+                                        // The bytes of a hex word of bytes of
+                                        // different classes are colored one by one
+                                        None if self.color
+                                            && matches!(conv.kind, ConvKind::Hex { .. }) =>
+                                        {
+                                            color::write_hex_bytes(
+                                                out,
+                                                &conv_text,
+                                                bytes,
+                                                conv.byte_count,
+                                                self.endian,
+                                            );
+                                        }
+                                        None => out.extend_from_slice(&conv_text),
+                                    }
+                                }
+                                pos += conv.byte_count;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Display the units containing %_A once all input has been processed
+    ///
+    /// Args:
+    ///   out - Buffer the formatted text is appended to
+    ///   address - The address just past the last byte of input
+    pub fn display_end(&self, out: &mut Vec<u8>, address: usize) {
+        let end_unit = self
+            .strings
+            .iter()
+            .flat_map(|s| s.units.iter())
+            .rev()
+            .find(|u| u.has_end_address());
+        if let Some(unit) = end_unit {
+            for piece in &unit.pieces {
+                match piece {
+                    Piece::Text(text) => out.extend_from_slice(text.as_bytes()),
+                    Piece::Conv(Conversion {
+                        spec,
+                        kind: ConvKind::EndAddress(radix),
+                        ..
+                    }) => write_address(out, spec, *radix, address),
+                    Piece::Conv(_) => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Input handling, several files read one after the other as one stream
+use crate::error::HdError;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+/// The combined length of the files, when it can be known without reading
+///
+/// Files that can't be opened count as empty, MultiReader reports them when
+/// they are read.
+///
+/// Args:
+///   files - The files, "-" for standard input
+/// Return:
+///   The total length in bytes, or None if any input is standard input or
+///   can't seek, like a pipe
+pub fn total_length(files: &[String]) -> Option<u64> {
+    let mut total = 0;
+    for name in files {
+        if name == "-" {
+            return None;
+        }
+        match std::fs::metadata(name) {
+            Ok(metadata) if metadata.is_file() => total += metadata.len(),
+            Ok(_) => return None,
+            Err(_) => {}
+        }
+    }
+    Some(total)
+}
+
+/// An open input, either a file or standard input ("-")
+enum Source {
+    Stdin(io::Stdin),
+    File(File),
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::Stdin(stdin) => stdin.read(buf),
+            Source::File(file) => file.read(buf),
+        }
+    }
+}
+
+/// The input currently being read
+struct Current {
+    name: String,
+    source: Source,
+    /// Length of a regular file, None for inputs that can't seek
+    length: Option<u64>,
+    /// Bytes left in a regular file, None for inputs that can't seek
+    remaining: Option<u64>,
+}
+
+/// Reads a list of files as one continuous stream
+///
+/// Files that can't be opened or read are skipped, the reason for each one
+/// is kept in errors.
+pub struct MultiReader {
+    files: VecDeque<String>,
+    current: Option<Current>,
+    /// Why each skipped file couldn't be opened or read
+    pub errors: Vec<HdError>,
+}
+
+impl MultiReader {
+    /// Create a reader over files, standard input is used for "-"
+    pub fn new(files: &[String]) -> MultiReader {
+        MultiReader {
+            files: files.iter().cloned().collect(),
+            current: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Record a file that couldn't be read and move on to the next one
+    fn fail(&mut self, err: HdError) {
+        self.errors.push(err);
+        self.current = None;
+    }
+
+    /// Open the next readable file
+    ///
+    /// Return:
+    ///   true if a file was opened, false when there are no files left
+    fn open_next(&mut self) -> bool {
+        while let Some(name) = self.files.pop_front() {
+            if name == "-" {
+                self.current = Some(Current {
+                    name,
+                    source: Source::Stdin(io::stdin()),
+                    length: None,
+                    remaining: None,
+                });
+                return true;
+            }
+            let opened = File::open(&name).and_then(|file| {
+                let metadata = file.metadata()?;
+                Ok((file, metadata))
+            });
+            match opened {
+                Ok((file, metadata)) => {
+                    // Pipes, character devices and the like can't seek
+                    let length = metadata.is_file().then_some(metadata.len());
+                    self.current = Some(Current {
+                        name,
+                        source: Source::File(file),
+                        length,
+                        remaining: length,
+                    });
+                    return true;
+                }
+                Err(err) => self.fail(HdError::from_io(&name, err)),
+            }
+        }
+        false
+    }
+
+    /// Skip offset bytes of the stream
+    ///
+    /// Regular files are skipped over or seeked in, any other input is read
+    /// and the bytes discarded.
+    ///
+    /// Args:
+    ///   offset - The number of bytes to skip
+    /// Return:
+    ///   bytes_skipped, less than offset if the stream is shorter
+    pub fn skip(&mut self, offset: usize) -> usize {
+        let mut left = offset as u64;
+        while left > 0 {
+            if self.current.is_none() && !self.open_next() {
+                break;
+            }
+            let Some(current) = self.current.as_mut() else {
+                break;
+            };
+            match (&mut current.source, current.remaining) {
+                (_, Some(remaining)) if remaining <= left => {
+                    left -= remaining;
+                    self.current = None;
+                }
+                (Source::File(file), Some(remaining)) => {
+                    if let Err(err) = file.seek(SeekFrom::Current(left as i64)) {
+                        let err = HdError::from_io(&current.name, err);
+                        self.fail(err);
+                        continue;
+                    }
+                    current.remaining = Some(remaining - left);
+                    left = 0;
+                }
+                (source, _) => match io::copy(&mut source.take(left), &mut io::sink()) {
+                    Ok(skipped) if skipped < left => {
+                        left -= skipped;
+                        self.current = None;
+                    }
+                    Ok(_) => left = 0,
+                    Err(err) => {
+                        let err = HdError::from_io(&current.name, err);
+                        self.fail(err);
+                    }
+                },
+            }
+        }
+        offset - left as usize
+    }
+}
+
+impl Read for MultiReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.current.is_none() && !self.open_next() {
+                return Ok(0);
+            }
+            let Some(current) = self.current.as_mut() else {
+                return Ok(0);
+            };
+            match current.source.read(buf) {
+                Ok(0) => match (current.length, current.remaining) {
+                    (Some(length), Some(remaining)) if remaining > 0 => {
+                        let err = HdError::ShortRead {
+                            path: current.name.clone(),
+                            expected: length,
+                            read: length - remaining,
+                        };
+                        self.fail(err);
+                    }
+                    _ => self.current = None,
+                },
+                Ok(n) => {
+                    current.remaining = current.remaining.map(|r| r.saturating_sub(n as u64));
+                    return Ok(n);
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    let err = HdError::from_io(&current.name, err);
+                    self.fail(err);
+                }
+            }
+        }
+    }
+}
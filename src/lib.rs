@@ -0,0 +1,655 @@
+//! hd is a hexdump clone written in Rust
+//!
+//! The library side of hd.  A Dumper is built from DumpOptions and dumps any
+//! `Read` source to any `Write` sink:
+//!
+//! ```
+//! use hd::{DumpOptions, Dumper};
+//!
+//! let options = DumpOptions {
+//!     canonical: true,
+//!     ..Default::default()
+//! };
+//! let mut out = Vec::new();
+//! let summary = Dumper::new(options).dump(&b"hello"[..], &mut out).unwrap();
+//! assert_eq!(summary.bytes_dumped, 5);
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     "00000000  68 65 6c 6c 6f                                    |hello|\n00000005\n"
+//! );
+//! ```
+use entropy::EntropyOptions;
+use error::HdError;
+use format::{Endian, Format, FormatString, Mode};
+use input::MultiReader;
+use search::FindOptions;
+use size::Region;
+use std::io;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use strings::StringsOptions;
+use xxd::{XxdOptions, XxdStyle};
+
+pub mod color;
+pub mod diff;
+pub mod entropy;
+pub mod error;
+pub mod format;
+pub mod input;
+pub mod reverse;
+pub mod search;
+pub mod size;
+pub mod strings;
+#[cfg(test)]
+mod tests;
+pub mod xxd;
+
+/// Options controlling what is dumped and how it is displayed
+#[derive(Clone, Debug, Default)]
+pub struct DumpOptions {
+    /// -C canonical hex+ASCII display
+    pub canonical: bool,
+    /// -c one-byte character display
+    pub one_byte_char: bool,
+    /// -b one-byte octal display
+    pub one_byte_octal: bool,
+    /// -v output identical lines
+    pub no_squeezing: bool,
+    /// -d two-bytes decimal display
+    pub two_bytes_dec: bool,
+    /// -o two-bytes octal display
+    pub two_bytes_octal: bool,
+    /// -x two-bytes hexadecimal display
+    pub two_bytes_hex: bool,
+    /// -e and -f format strings, displayed after any of the modes above
+    pub formats: Vec<FormatString>,
+    /// -s number of bytes to skip from the beginning of the input
+    pub skip: usize,
+    /// -s with a minus sign, skip counts back from the end of the input
+    pub skip_from_end: bool,
+    /// -n number of bytes to dump, None for everything up to the end
+    pub length: Option<usize>,
+    /// --range regions dumped one after the other instead of skip and length
+    pub ranges: Vec<Region>,
+    /// --xxd display, replaces all of the display modes above
+    pub xxd: Option<XxdOptions>,
+    /// -w bytes per line of the display modes, None for 16, or 8 with
+    /// --binary
+    pub width: Option<usize>,
+    /// --group bytes shown as one number by the numeric display modes, None
+    /// for the size of the mode
+    pub group: Option<usize>,
+    /// --endian byte order of the multi-byte values of the display modes and
+    /// format strings
+    pub endian: Endian,
+    /// Display modes shown after the ones of the flags above, one row each,
+    /// e.g. Mode::Int(IntKind::Hex, 4) for -t x4
+    pub modes: Vec<Mode>,
+    /// --color the bytes by class with ANSI colors
+    pub color: bool,
+    /// --find only show the matches of a pattern
+    pub find: Option<FindOptions>,
+    /// --strings print the runs of printable characters instead of a dump
+    pub strings: Option<StringsOptions>,
+    /// --entropy and --histogram report how the byte values are spread
+    /// instead of a dump
+    pub entropy: Option<EntropyOptions>,
+}
+
+impl DumpOptions {
+    /// The built-in display modes selected by the options, each one prints
+    /// a row for every line of input
+    ///
+    /// Return:
+    ///   The modes of the flags, in the order -C, -x, -b, -o, -d, -c, then
+    ///   the modes in `modes`.  The default display if there are no modes or
+    ///   format strings, or nothing for format strings on their own
+    pub fn modes(&self) -> Vec<Mode> {
+        let flags = [
+            (self.canonical, Mode::Canonical),
+            (self.two_bytes_hex, Mode::TwoBytesHex),
+            (self.one_byte_octal, Mode::OneByteOctal),
+            (self.two_bytes_octal, Mode::TwoBytesOctal),
+            (self.two_bytes_dec, Mode::TwoBytesDec),
+            (self.one_byte_char, Mode::OneByteChar),
+        ];
+        let mut modes: Vec<Mode> = flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, mode)| *mode)
+            .chain(self.modes.iter().copied())
+            .collect();
+        if modes.is_empty() && self.formats.is_empty() {
+            modes.push(Mode::Default);
+        }
+        modes
+    }
+
+    /// Bytes per line of the display modes
+    ///
+    /// Return:
+    ///   The width, or the narrowest default width of the modes if none was
+    ///   given
+    pub fn line_width(&self) -> usize {
+        self.width.unwrap_or_else(|| {
+            self.modes()
+                .iter()
+                .map(Mode::default_width)
+                .min()
+                .unwrap_or(format::DEFAULT_WIDTH)
+        })
+    }
+}
+
+/// What a finished dump did
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DumpSummary {
+    /// Number of bytes of input that were displayed
+    pub bytes_dumped: usize,
+    /// The address just past the last byte displayed
+    pub end_address: usize,
+    /// Number of lines displayed, not counting the '*' squeeze markers
+    pub lines_printed: usize,
+    /// Number of lines left out because they repeat the line before them
+    pub lines_squeezed: usize,
+}
+
+/// Dumps input in the format described by DumpOptions
+#[derive(Clone, Debug)]
+pub struct Dumper {
+    options: DumpOptions,
+    format: Format,
+}
+
+/// The character shown for a byte on the right side of the dump w/ -C
+///
+/// Args:
+///   byte - The byte to show
+/// Return:
+///   The byte itself if it is printable ASCII, '.' otherwise
+pub fn printable_byte(byte: u8) -> u8 {
+    if (0x20..=0x7E).contains(&byte) {
+        byte
+    } else {
+        b'.'
+    }
+}
+
+/// Convert a u8 array into a String, for the right side of the dump w/ -C
+///
+/// Args:
+///   raw_line - The raw line to convert to a string
+/// Return:
+///   conv_line - The line as an ascii formatted string
+pub fn convert_to_string(raw_line: &[u8]) -> String {
+    raw_line
+        .iter()
+        .map(|byte| printable_byte(*byte) as char)
+        .collect()
+}
+
+/// Check to see if two buffers equal each other
+///
+/// Args:
+///   b1 - buffer one
+///   b2 - buffer two
+/// Return:
+///   true if the buffers match, false otherwise
+pub fn vecs_match(b1: &[u8], b2: &[u8]) -> bool {
+    for (i, item) in b1.iter().enumerate() {
+        if b2[i] != *item {
+            return false;
+        }
+    }
+    true
+}
+
+/// Build the Format used to display the input from the options
+///
+/// Args:
+///   options - The format options passed in
+/// Return:
+///   The Format for the selected display modes followed by any format
+///   strings from -e or -f
+fn build_format(options: &DumpOptions) -> Format {
+    if let Some(xxd) = &options.xxd {
+        return Format::new(xxd.format_strings()).with_color(options.color);
+    }
+    // The first mode prints the addresses, the rest are rows under it
+    let width = options.line_width();
+    let builtin: Vec<String> = options
+        .modes()
+        .iter()
+        .enumerate()
+        .flat_map(|(i, mode)| match i {
+            0 => mode.format_strings(width, options.group),
+            _ => mode.row_format_strings(width, options.group),
+        })
+        .collect();
+    let mut strings =
+        format::parse_format_strings(&builtin).expect("built-in format strings are valid");
+    strings.extend(options.formats.iter().cloned());
+    Format::new(strings)
+        .with_endian(options.endian)
+        .with_color(options.color)
+}
+
+/// Size of the reads from the input, rounded down to a multiple of the block size
+const READ_BUF_LEN: usize = 64 * 1024;
+
+/// Size of the buffer between the dump and stdout
+pub const WRITE_BUF_LEN: usize = 64 * 1024;
+
+/// Function to print out each line
+///
+/// Args:
+///   out - The sink to print the line to
+///   buf - Scratch buffer the line is formatted into, reused for every line
+///   line - The line to hexdump
+///   address - The address to print on the left side
+///   format - The format used to display the line
+/// Return:
+///   Result(()) if the line is printed, Err(HdError::Write) if the write fails
+fn print_bin<W: Write>(
+    out: &mut W,
+    buf: &mut Vec<u8>,
+    line: &[u8],
+    address: usize,
+    format: &Format,
+) -> Result<(), HdError> {
+    debug_assert!(line.len() <= format.block_size());
+    buf.clear();
+    format.display(buf, line, address);
+    out.write_all(buf).map_err(HdError::Write)
+}
+
+/// Read into buf until it is full or the input ends
+///
+/// Args:
+///   reader - The input to read from
+///   buf - The buffer to fill
+/// Return:
+///   Result(bytes_read), less than buf.len() only at the end of the input
+pub fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(total)
+}
+
+/// The error returned when reading a plain Read source fails
+fn read_error(source: io::Error) -> HdError {
+    HdError::Read { path: None, source }
+}
+
+/// Read a source to the end, keeping only its last bytes
+///
+/// Used for -s with a minus sign when the input can't seek.
+///
+/// Args:
+///   reader - The input
+///   count - Number of bytes to keep from the end
+/// Return:
+///   Result((tail, offset)) the last count bytes, or all of them if the
+///   input is shorter, and the offset of the first one, or Err(HdError) if
+///   reading fails
+fn read_tail<R: Read>(reader: &mut R, count: usize) -> Result<(Vec<u8>, usize), HdError> {
+    let mut tail = Vec::new();
+    let mut buf = vec![0u8; READ_BUF_LEN];
+    let mut total = 0;
+    loop {
+        let bytes_read = read_full(reader, &mut buf).map_err(read_error)?;
+        tail.extend_from_slice(&buf[..bytes_read]);
+        total += bytes_read;
+        // Dropping the front only once it has grown past twice what is kept
+        // stops a large count from being copied on every read
+        if tail.len() >= count.max(READ_BUF_LEN).saturating_mul(2) {
+            tail.drain(..tail.len() - count);
+        }
+        if bytes_read < buf.len() {
+            break;
+        }
+    }
+    let keep = tail.len().min(count);
+    tail.drain(..tail.len() - keep);
+    Ok((tail, total - keep))
+}
+
+impl Dumper {
+    /// Create a Dumper for the given options
+    pub fn new(options: DumpOptions) -> Dumper {
+        let format = build_format(&options);
+        Dumper { options, format }
+    }
+
+    /// The options this Dumper was built from
+    pub fn options(&self) -> &DumpOptions {
+        &self.options
+    }
+
+    /// Dump a source that can't seek, skipping bytes by reading them
+    ///
+    /// Args:
+    ///   reader - The input to dump
+    ///   writer - The sink the dump is written to
+    /// Return:
+    ///   Result(DumpSummary) if success, or Err(HdError) if reading or
+    ///   writing fails or the input is shorter than the skip offset
+    pub fn dump<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        writer: W,
+    ) -> Result<DumpSummary, HdError> {
+        if self.options.skip_from_end {
+            let (tail, offset) = read_tail(&mut reader, self.options.skip)?;
+            return self.dump_from(&tail[..], writer, offset);
+        }
+        let offset = self.options.skip;
+        let skipped = io::copy(&mut (&mut reader).take(offset as u64), &mut io::sink())
+            .map_err(read_error)?;
+        if skipped < offset as u64 {
+            return Err(HdError::OffsetPastEnd {
+                offset,
+                input_length: skipped as usize,
+            });
+        }
+        self.dump_from(reader, writer, offset)
+    }
+
+    /// Dump a source that can seek, seeking past the skipped bytes
+    ///
+    /// Args:
+    ///   reader - The input to dump, starting at its current position
+    ///   writer - The sink the dump is written to
+    /// Return:
+    ///   Result(DumpSummary) if success, or Err(HdError) if reading or
+    ///   writing fails or the input is shorter than the skip offset
+    pub fn dump_seekable<R: Read + Seek, W: Write>(
+        &self,
+        mut reader: R,
+        writer: W,
+    ) -> Result<DumpSummary, HdError> {
+        let start = reader.stream_position().map_err(read_error)?;
+        let end = reader.seek(SeekFrom::End(0)).map_err(read_error)?;
+        let input_length = end.saturating_sub(start);
+        let offset = match self.options.skip_from_end {
+            true => (input_length as usize).saturating_sub(self.options.skip),
+            false => self.options.skip,
+        };
+        if offset as u64 > input_length {
+            return Err(HdError::OffsetPastEnd {
+                offset,
+                input_length: input_length as usize,
+            });
+        }
+        reader
+            .seek(SeekFrom::Start(start + offset as u64))
+            .map_err(read_error)?;
+        self.dump_from(reader, writer, offset)
+    }
+
+    /// Dump a source from its current position, without skipping anything
+    ///
+    /// The length of the input doesn't need to be known up front, the dump
+    /// ends after the requested length or at the end of the input.
+    ///
+    /// Args:
+    ///   reader - The input, already positioned at the first byte to dump
+    ///   writer - The sink the dump is written to
+    ///   offset - The address of the first byte read from reader
+    /// Return:
+    ///   Result(DumpSummary) if success, or Err(HdError) if reading or
+    ///   writing fails
+    pub fn dump_from<R: Read, W: Write>(
+        &self,
+        mut reader: R,
+        mut writer: W,
+        offset: usize,
+    ) -> Result<DumpSummary, HdError> {
+        let xxd = self.options.xxd.as_ref();
+        if let Some(xxd) = xxd.filter(|xxd| xxd.style == XxdStyle::Include) {
+            let bytes_dumped = xxd::dump_include(xxd, reader, writer, self.options.length)?;
+            return Ok(DumpSummary {
+                bytes_dumped,
+                end_address: offset + bytes_dumped,
+                lines_printed: bytes_dumped.div_ceil(xxd.cols.max(1)),
+                lines_squeezed: 0,
+            });
+        }
+        if let Some(entropy) = &self.options.entropy {
+            let length = self.options.length;
+            return entropy::dump_entropy(entropy, reader, writer, offset, length);
+        }
+        if let Some(strings) = &self.options.strings {
+            let length = self.options.length;
+            return strings::dump_strings(strings, reader, writer, offset, length);
+        }
+        if let Some(find) = &self.options.find {
+            let length = self.options.length;
+            return if find.list {
+                search::list_matches(find, reader, writer, offset, length)
+            } else {
+                search::dump_matches(find, &self.format, reader, writer, offset, length)
+            };
+        }
+        let format = &self.format;
+        let block_size = format.block_size();
+        let read_len = (READ_BUF_LEN / block_size).max(1) * block_size;
+        let mut input: Vec<u8> = vec![0; read_len];
+        let mut text: Vec<u8> = Vec::new();
+        let mut _prev_line: Vec<u8> = vec![0; block_size];
+        let mut _is_prev_line_set = false;
+        let mut bytes_left_to_dump: usize = self.options.length.unwrap_or(usize::MAX);
+        let mut address: usize = offset;
+        let mut lines_printed: usize = 0;
+        let mut lines_squeezed: usize = 0;
+
+        if bytes_left_to_dump == 0 {
+            return Ok(DumpSummary {
+                end_address: address,
+                ..Default::default()
+            });
+        }
+
+        // xxd never squeezes repeated lines
+        let squeeze =
+            !self.options.no_squeezing && self.options.xxd.is_none() && format.displays_input();
+        let mut _is_skip_line_printed = false;
+        while bytes_left_to_dump > 0 {
+            let want = std::cmp::min(read_len, bytes_left_to_dump);
+            let bytes_read = read_full(&mut reader, &mut input[..want]).map_err(read_error)?;
+            if bytes_read == 0 {
+                break;
+            }
+            bytes_left_to_dump -= bytes_read;
+            for line in input[..bytes_read].chunks(block_size) {
+                // Print a subline at the end of the input or of the requested length
+                if line.len() < block_size {
+                    print_bin(&mut writer, &mut text, line, address, format)?;
+                    lines_printed += 1;
+                    address += line.len();
+                    break;
+                }
+                // Normal case, a full block
+                let _is_line_same = _is_prev_line_set && squeeze && vecs_match(line, &_prev_line);
+                if _is_line_same && !_is_skip_line_printed {
+                    writer.write_all(b"*\n").map_err(HdError::Write)?;
+                    _is_skip_line_printed = true;
+                    lines_squeezed += 1;
+                // This line matched the previous line so skip printing.
+                } else if _is_line_same && _is_skip_line_printed {
+                    lines_squeezed += 1;
+                } else {
+                    print_bin(&mut writer, &mut text, line, address, format)?;
+                    lines_printed += 1;
+                    _is_skip_line_printed = false;
+                    _prev_line.copy_from_slice(line);
+                    _is_prev_line_set = true;
+                }
+                address += block_size;
+            }
+            if bytes_read < want {
+                break;
+            }
+        }
+        // Print the %_A end address units once all input is processed
+        if address != 0 {
+            text.clear();
+            format.display_end(&mut text, address);
+            writer.write_all(&text).map_err(HdError::Write)?;
+        }
+        writer.flush().map_err(HdError::Write)?;
+        Ok(DumpSummary {
+            bytes_dumped: address - offset,
+            end_address: address,
+            lines_printed,
+            lines_squeezed,
+        })
+    }
+}
+
+/// Open the files and dump their content to writer as one continuous stream
+///
+/// Files that can't be read are skipped, the rest are still dumped with
+/// addresses running on from the previous file.
+///
+/// Args:
+///   files - The files to hexdump, "-" for standard input
+///   options - The format/number options passed in
+///   writer - The sink the dump is written to
+/// Return:
+///   Result(DumpSummary) if succes, or Err(HdError) if the dump failed or
+///   HdError::Inputs if any file was skipped
+pub fn dump_files<W: Write>(
+    files: &[String],
+    options: &DumpOptions,
+    writer: W,
+) -> Result<DumpSummary, HdError> {
+    if !options.ranges.is_empty() {
+        return dump_ranges(files, options, writer);
+    }
+    let mut input = MultiReader::new(files);
+    let dumper = Dumper::new(options.clone());
+    let mut offset = options.skip;
+    if options.skip_from_end {
+        match input::total_length(files) {
+            Some(length) => offset = (length as usize).saturating_sub(offset),
+            None => {
+                // Standard input and pipes can't seek, so the end is kept as
+                // they are read
+                let (tail, offset) = read_tail(&mut input, options.skip)?;
+                let summary = dumper.dump_from(&tail[..], writer, offset)?;
+                if !input.errors.is_empty() {
+                    return Err(HdError::Inputs(input.errors));
+                }
+                return Ok(summary);
+            }
+        }
+    }
+    let skipped = input.skip(offset);
+    if skipped < offset {
+        // A missing file is a better explanation than the offset being too big
+        if !input.errors.is_empty() {
+            return Err(HdError::Inputs(input.errors));
+        }
+        return Err(HdError::OffsetPastEnd {
+            offset,
+            input_length: skipped,
+        });
+    }
+
+    let summary = dumper.dump_from(&mut input, writer, offset)?;
+    if !input.errors.is_empty() {
+        return Err(HdError::Inputs(input.errors));
+    }
+    Ok(summary)
+}
+
+/// Open the files and dump each --range region, with "--" between them
+///
+/// The regions are read from one stream that skips, seeking where it can,
+/// from the end of a region to the start of the next.  A region that starts
+/// before the end of the one before opens the files again, so that needs
+/// inputs that can seek.  Addresses are the offsets in the whole stream.
+///
+/// Args:
+///   files - The files to hexdump, "-" for standard input
+///   options - The format/number options passed in, with the regions
+///   writer - The sink the dump is written to
+/// Return:
+///   Result(DumpSummary) of all of the regions, or Err(HdError) if a dump
+///   failed, a region starts past the end of the input or HdError::Inputs if
+///   any file was skipped
+fn dump_ranges<W: Write>(
+    files: &[String],
+    options: &DumpOptions,
+    mut writer: W,
+) -> Result<DumpSummary, HdError> {
+    let mut input = MultiReader::new(files);
+    // Files that can't be read fail again when they are opened again, only
+    // the errors of the first pass are kept
+    let mut first_errors: Option<Vec<HdError>> = None;
+    let mut position = 0;
+    let mut total = DumpSummary::default();
+    for (i, region) in options.ranges.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b"--\n").map_err(HdError::Write)?;
+        }
+        if region.start < position {
+            let old = std::mem::replace(&mut input, MultiReader::new(files));
+            first_errors.get_or_insert(old.errors);
+            position = 0;
+        }
+        let gap = region.start - position;
+        let skipped = input.skip(gap);
+        if skipped < gap {
+            let errors = first_errors.unwrap_or(input.errors);
+            if !errors.is_empty() {
+                return Err(HdError::Inputs(errors));
+            }
+            return Err(HdError::OffsetPastEnd {
+                offset: region.start,
+                input_length: position + skipped,
+            });
+        }
+        let dumper = Dumper::new(DumpOptions {
+            skip: region.start,
+            skip_from_end: false,
+            length: region.length,
+            ranges: Vec::new(),
+            ..options.clone()
+        });
+        let summary = dumper.dump_from(&mut input, &mut writer, region.start)?;
+        position = summary.end_address;
+        total = DumpSummary {
+            bytes_dumped: total.bytes_dumped + summary.bytes_dumped,
+            end_address: summary.end_address,
+            lines_printed: total.lines_printed + summary.lines_printed,
+            lines_squeezed: total.lines_squeezed + summary.lines_squeezed,
+        };
+    }
+    let errors = first_errors.unwrap_or(input.errors);
+    if !errors.is_empty() {
+        return Err(HdError::Inputs(errors));
+    }
+    Ok(total)
+}
+
+/// Dump the files as one continuous stream for the tests, the dump itself is
+/// thrown away
+///
+/// Args:
+///   files - The files to hexdump, "-" for standard input
+///   options - The format/number options passed in
+/// Return:
+///   Result(bytes_dumped) if succes, or Err(HdError) if the dump failed or
+///   HdError::Inputs if any file was skipped
+#[cfg(test)]
+fn hexdump(files: &[String], options: &DumpOptions) -> Result<usize, HdError> {
+    dump_files(files, options, Vec::new()).map(|summary| summary.bytes_dumped)
+}
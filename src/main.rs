@@ -1,34 +1,41 @@
-//! hd is a hexdump clone written in Rust
-use clap::Parser;
-use clap_num::maybe_hex;
-use std::fs::File;
+//! hd is a hexdump clone written in Rust, the command line front end
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use hd::color;
+use hd::diff::{self, DiffOptions};
+use hd::entropy::{DEFAULT_BLOCK_SIZE, EntropyOptions};
+use hd::error::{self, HdError};
+use hd::format::{self, Endian, FormatString, Mode};
+use hd::input::{self, MultiReader};
+use hd::reverse;
+use hd::search::{FindOptions, Pattern};
+use hd::size::{self, Offset, Region};
+use hd::strings::{Encoding, StringsOptions};
+use hd::xxd::{self, XxdOptions, XxdStyle};
+use hd::{DumpOptions, DumpSummary, WRITE_BUF_LEN, dump_files};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::Seek;
-use std::io::SeekFrom;
-use std::io::{Error, ErrorKind, Read};
-use std::path::Path;
-use std::str;
-use std::time::Instant;
+use std::io::{BufReader, ErrorKind, Write};
+use std::time::{Duration, Instant};
 
-#[cfg(test)]
-mod tests;
-
-/// READ_LEN constant used to check bounds and setup read buffers
-const READ_LEN: usize = 0x10;
-
-#[derive(Clone)]
-struct HdOptions {
-    canonical: bool,
-    one_byte_char: bool,
-    one_byte_octal: bool,
-    no_squeezing: bool,
-    two_bytes_dec: bool,
-    two_bytes_octal: bool,
-    two_bytes_hex: bool,
-}
+/// Exit codes listed at the end of --help, see hd::error
+const EXIT_STATUS_HELP: &str = "\
+Exit status:
+  0  the dump succeeded
+  1  reading the input failed
+  2  invalid command line arguments
+  3  a file does not exist
+  4  permission denied opening or reading a file
+  5  the skip offset is past the end of the input
+  6  a file got shorter while it was being read
+  7  writing the dump failed
+  8  a format string or format file is invalid
+  9  the dump given to -r can't be parsed
+ 10  the files given to --diff are different";
 
 #[derive(Parser)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_STATUS_HELP)]
 struct Args {
     /// one-byte octal display
     #[arg(short = 'b', long)]
@@ -48,425 +55,628 @@ struct Args {
     /// two-byte hexadecimal display
     #[arg(short = 'x', long)]
     two_bytes_hex: bool,
-    /// interpret only length bytes of input
-    #[arg(short = 'n', long, value_parser=maybe_hex::<usize>)]
+    /// format string to be used for displaying data
+    #[arg(short = 'e', long = "format")]
+    formats: Vec<String>,
+    /// file that contains format strings
+    #[arg(short = 'f', long = "format-file")]
+    format_files: Vec<String>,
+    /// interpret only length bytes of input, decimal, 0x hex or 0 octal with
+    /// an optional suffix: b (512), k, KiB, M, MiB, G, GiB, e.g. -n 4KiB
+    #[arg(short = 'n', long, value_parser = size::parse_size)]
     length: Option<usize>,
-    /// skip offset bytes from the beginning of the input
-    #[arg(short = 's', long, value_parser=maybe_hex::<usize>)]
-    skip: Option<usize>,
-    /// file to hexdump
-    file: String,
+    /// skip offset bytes from the beginning of the input, or with a minus
+    /// sign start offset bytes before the end, e.g. -s 1M or -s -64
+    #[arg(
+        short = 's',
+        long,
+        value_parser = size::parse_offset,
+        allow_hyphen_values = true
+    )]
+    skip: Option<Offset>,
+    /// dump only a region of the input, START:END or START+LEN, e.g.
+    /// --range 0x1be+64; repeat it to dump several regions with "--"
+    /// between them
+    #[arg(
+        long = "range",
+        value_name = "START:END|START+LEN",
+        value_parser = size::parse_range,
+        conflicts_with_all = ["skip", "length", "diff", "reverse"]
+    )]
+    ranges: Vec<Region>,
+    /// files to hexdump, standard input if none or "-"
+    files: Vec<String>,
     /// output identical lines
     #[arg(short = 'v', long)]
     no_squeezing: bool,
+    /// bytes per line of the display modes, 16 by default, 8 for --binary
+    #[arg(short = 'w', long, value_parser = clap::value_parser!(u16).range(1..))]
+    width: Option<u16>,
+    /// bytes shown as one number in the numeric display modes: 1, 2, 4, 8 or 16
+    #[arg(long, value_parser = ["1", "2", "4", "8", "16"])]
+    group: Option<String>,
+    /// byte order of multi-byte values in the display modes and format strings
+    #[arg(long, value_parser = ["little", "big", "native"], default_value = "little")]
+    endian: String,
+    /// od style display type: x, d, u or o followed by 1, 2, 4, 8 or 16 bytes
+    /// (or C, S, I, L), or f followed by 2, 4 or 8 bytes, e.g. -t x4, -t d2,
+    /// -t dC or -t f4
+    #[arg(short = 't', long = "type")]
+    types: Vec<Mode>,
+    /// color bytes by class: auto (when stdout is a terminal and NO_COLOR
+    /// isn't set), always or never
+    #[arg(
+        long,
+        value_name = "WHEN",
+        value_parser = ["auto", "always", "never"],
+        default_value = "auto",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "always"
+    )]
+    color: String,
+    /// only show the lines around the matches of a pattern, hex bytes with ??
+    /// for any byte by default, e.g. --find '7f 45 4c ?? 02'
+    #[arg(long, value_name = "PATTERN")]
+    find: Option<String>,
+    /// how --find reads its pattern: hex, ascii or utf16 (in the --endian
+    /// byte order)
+    #[arg(long, value_parser = ["hex", "ascii", "utf16"], default_value = "hex", requires = "find")]
+    find_as: String,
+    /// list the offset of each --find match instead of the dump around it
+    #[arg(long, requires = "find")]
+    list: bool,
+    /// lines shown before and after each --find match
+    #[arg(long, value_name = "LINES", default_value_t = 1, requires = "find")]
+    context: usize,
+    /// print the runs of at least N printable characters, 4 by default,
+    /// with their offsets instead of a dump
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "4",
+        conflicts_with_all = ["find", "diff", "reverse"]
+    )]
+    strings: Option<usize>,
+    /// character encoding --strings scans for: ascii, utf8, utf16le or utf16be
+    #[arg(long, value_parser = ["ascii", "utf8", "utf16le", "utf16be"], default_value = "ascii", requires = "strings")]
+    encoding: String,
+    /// radix of the --strings offsets: hex or dec
+    #[arg(long, value_parser = ["hex", "dec"], default_value = "hex", requires = "strings")]
+    radix: String,
+    /// print a graph of the entropy of each block and then the Shannon
+    /// entropy of the input instead of a dump
+    #[arg(long, conflicts_with_all = ["find", "strings", "diff", "reverse"])]
+    entropy: bool,
+    /// print the count of each byte value instead of a dump
+    #[arg(long, conflicts_with_all = ["find", "strings", "diff", "reverse"])]
+    histogram: bool,
+    /// bytes per row of the --entropy graph
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = size::parse_size,
+        default_value_t = DEFAULT_BLOCK_SIZE,
+        requires = "entropy"
+    )]
+    block_size: usize,
+    /// compare two files side by side, exit status 0 if they are the same
+    /// and 10 if they differ
+    #[arg(long, conflicts_with_all = ["reverse", "find"])]
+    diff: bool,
+    /// list the ranges of bytes that differ instead of the --diff dump
+    #[arg(long, requires = "diff")]
+    summary: bool,
+    /// binary display, every byte as 8 bits
+    #[arg(short = 'B', long)]
+    binary: bool,
+    /// put a space between the nibbles of the --binary display
+    #[arg(long, requires = "binary")]
+    nibbles: bool,
+    /// add an ASCII column to the --binary display
+    #[arg(long, requires = "binary")]
+    ascii: bool,
+    /// significant digits of the -t f float types, by default enough to tell
+    /// every value apart: 5, 9 and 17 for f2, f4 and f8
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=40))]
+    precision: Option<u8>,
+    /// print the options and dump statistics to stderr
+    #[arg(long, visible_alias = "verbose")]
+    stats: bool,
+    /// xxd compatible output, see hd --xxd --help for its options
+    #[arg(long)]
+    xxd: bool,
+    /// reverse, rebuild the binary from a -C, default, xxd or plain hex dump
+    #[arg(short = 'r', long)]
+    reverse: bool,
 }
 
-/// Convert a u8 array into a String, for the right side of the dump w/ -C
+/// The command line of hd --xxd, which takes xxd's options instead of hd's
+#[derive(Parser)]
+#[command(name = "hd --xxd", version, about = "xxd compatible hex dump", after_help = EXIT_STATUS_HELP)]
+struct XxdArgs {
+    /// xxd compatible output
+    #[arg(long, required = true)]
+    xxd: bool,
+    /// octets per line, 16 by default, 30 with -p and 12 with -i
+    #[arg(short = 'c', long, value_parser = clap::value_parser!(u16).range(1..=xxd::MAX_COLS as i64))]
+    cols: Option<u16>,
+    /// octets per group in the normal output, 0 for no grouping
+    #[arg(short = 'g', long = "groupsize", default_value_t = xxd::DEFAULT_GROUP)]
+    group: usize,
+    /// use upper case hex letters
+    #[arg(short = 'u', long)]
+    upper: bool,
+    /// output in postscript plain hexdump style
+    #[arg(short = 'p', long = "ps", visible_aliases = ["postscript", "plain"])]
+    plain: bool,
+    /// output in C include file style
+    #[arg(short = 'i', long, conflicts_with = "plain")]
+    include: bool,
+    /// reverse, rebuild the binary from a dump, patching outfile in place
+    #[arg(short = 'r', long = "revert", conflicts_with = "include")]
+    reverse: bool,
+    /// variable name used by -i, taken from the input file by default
+    #[arg(short = 'n', long)]
+    name: Option<String>,
+    /// start at seek bytes from the beginning of the input, or with a minus
+    /// sign seek bytes before the end
+    #[arg(
+        short = 's',
+        long,
+        value_parser = size::parse_offset,
+        allow_hyphen_values = true
+    )]
+    seek: Option<Offset>,
+    /// stop after len octets
+    #[arg(short = 'l', long = "len", value_parser = size::parse_size)]
+    length: Option<usize>,
+    /// color the output by byte class: auto, always or never
+    #[arg(short = 'R', long, value_parser = ["auto", "always", "never"], default_value = "auto")]
+    color: String,
+    /// file to dump, standard input if none or "-"
+    infile: Option<String>,
+    /// file to write the dump to, standard output if none or "-"
+    outfile: Option<String>,
+}
+
+/// Parse the -e format strings and the contents of the -f format files
 ///
 /// Args:
-///   raw_line - The raw line to convert to a string
+///   args - The command line arguments
 /// Return:
-///   conv_line - The line as an ascii formatted string
-fn convert_to_string(raw_line: &[u8]) -> String {
-    let mut conv_line: String = Default::default();
-    for i in 0..raw_line.len() {
-        let raw_char_as_str = str::from_utf8(&raw_line[i..i + 1]);
-        let raw_char = raw_line[i];
-        if (0x20..=0x7E).contains(&raw_char) {
-            // TODO: Fix this .unwrap() replace with '?'
-            conv_line.push_str(raw_char_as_str.unwrap());
-        } else {
-            conv_line.push('.');
-        }
+///   The parsed format strings, or Err(HdError) describing the problem
+fn parse_formats(args: &Args) -> Result<Vec<FormatString>, HdError> {
+    let mut formats = Vec::new();
+    for s in &args.formats {
+        let string = format::parse_format_string(s).map_err(|error| HdError::Format {
+            source: s.clone(),
+            error,
+        })?;
+        formats.push(string);
     }
-    conv_line
+    for file in &args.format_files {
+        let contents = std::fs::read_to_string(file).map_err(|err| HdError::from_io(file, err))?;
+        let mut strings =
+            format::parse_format_file(file, &contents).map_err(|error| HdError::Format {
+                source: file.clone(),
+                error,
+            })?;
+        formats.append(&mut strings);
+    }
+    Ok(formats)
 }
 
-/// Check to see if two buffers equal each other
+/// Compare the two files given to --diff and exit
 ///
 /// Args:
-///   b1 - buffer one
-///   b2 - buffer two
-/// Return:
-///   true if the buffers match, false otherwise
-fn vecs_match(b1: &[u8], b2: &[u8]) -> bool {
-    for (i, item) in b1.iter().enumerate() {
-        if b2[i] != *item {
-            return false;
+///   args - The command line arguments
+///   length - Bytes to compare, 0 for everything
+///   skip - Bytes to skip at the start of both files
+///   color - Color the bytes by class
+fn diff_main(args: &Args, length: usize, skip: usize, color: bool) -> ! {
+    let [a, b] = args.files.as_slice() else {
+        usage_error("--diff needs two files");
+    };
+    let options = DiffOptions {
+        names: [a.clone(), b.clone()],
+        width: args.width.map_or(format::DEFAULT_WIDTH, usize::from),
+        summary: args.summary,
+        no_squeezing: args.no_squeezing,
+        color,
+    };
+    let length = if length == 0 { None } else { Some(length) };
+    let stdout = io::BufWriter::with_capacity(WRITE_BUF_LEN, io::stdout().lock());
+    match diff::diff_files(&options, skip, length, stdout) {
+        Ok(0) => std::process::exit(error::EXIT_SUCCESS),
+        Ok(_) => std::process::exit(error::EXIT_DIFFERENT),
+        Err(err) => {
+            report(&err);
+            std::process::exit(err.exit_code());
         }
     }
-    true
 }
 
-/// Handle formatting for the "one-byte char" or -c format option
+/// Report an invalid argument the way clap does and exit with status 2
+fn usage_error(message: &str) -> ! {
+    Args::command()
+        .error(clap::error::ErrorKind::ValueValidation, message)
+        .exit()
+}
+
+/// The display modes in the order they were given on the command line
 ///
 /// Args:
-///   new_line: &String - The start of the format line
-///   line: &[u8] - The line to be hexdumped.
-///   i: usize - The index into line
-/// Return:
-///   fmt_line: String - The fmt_line to display
-fn format_one_byte_char(new_line: &String, line: &[u8], i: usize) -> String {
-    let mut fmt_line = String::new();
-    if line[i] == 0 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\0", new_line, spaces};
-    } else if line[i] == 7 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\a", new_line, spaces};
-    } else if line[i] == 8 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\b", new_line, spaces};
-    } else if line[i] == 9 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\t", new_line, spaces};
-    } else if line[i] == 10 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\n", new_line, spaces};
-    } else if line[i] == 11 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\v", new_line, spaces};
-    } else if line[i] == 12 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\f", new_line, spaces};
-    } else if line[i] == 13 {
-        let spaces = "  ";
-        fmt_line = format! {"{}{}\\r", new_line, spaces};
-    } else if line[i] == 123 {
-        let spaces = "   ";
-        fmt_line = format! {"{}{}{{", new_line, spaces};
-    } else if line[i] == 124 {
-        let spaces = "   ";
-        fmt_line = format! {"{}{}|", new_line, spaces};
-    } else if line[i] == 125 {
-        let spaces = "   ";
-        fmt_line = format! {"{}{}}}", new_line, spaces};
-    } else if line[i] == 126 {
-        let spaces = "   ";
-        fmt_line = format! {"{}{}~", new_line, spaces};
-    } else if line[i] == 1
-        || line[i] == 2
-        || line[i] == 3
-        || line[i] == 4
-        || line[i] == 5
-        || line[i] == 6
-        || line[i] == 14
-        || line[i] == 15
-        || line[i] == 16
-        || line[i] == 17
-        || line[i] == 18
-        || line[i] == 19
-        || line[i] == 20
-        || line[i] == 21
-        || line[i] == 22
-        || line[i] == 23
-        || line[i] == 24
-        || line[i] == 25
-        || line[i] == 26
-        || line[i] == 27
-        || line[i] == 28
-        || line[i] == 29
-        || line[i] == 30
-        || line[i] == 31
-        || line[i] > 126
-    {
-        let spaces = " ";
-        fmt_line = format! {"{}{}{:03o}", new_line, spaces, line[i]};
-    } else if line[i] < 123 {
-        let spaces = "   ";
-        let char = char::from(line[i]);
-        fmt_line = format! {"{}{}{}", new_line, spaces, char};
+///   args - The parsed arguments
+///   matches - The matches args was parsed from, which hold the positions
+fn display_modes(args: &Args, matches: &ArgMatches) -> Vec<Mode> {
+    let position = |id: &str| match matches.value_source(id) {
+        Some(ValueSource::CommandLine) => matches.index_of(id),
+        _ => None,
+    };
+    let flags = [
+        ("canonical", Mode::Canonical),
+        ("two_bytes_hex", Mode::TwoBytesHex),
+        ("one_byte_octal", Mode::OneByteOctal),
+        ("two_bytes_octal", Mode::TwoBytesOctal),
+        ("two_bytes_dec", Mode::TwoBytesDec),
+        ("one_byte_char", Mode::OneByteChar),
+        (
+            "binary",
+            Mode::Binary {
+                nibbles: args.nibbles,
+                ascii: args.ascii,
+            },
+        ),
+    ];
+    let mut modes: Vec<(usize, Mode)> = flags
+        .iter()
+        .filter_map(|(id, mode)| position(id).map(|i| (i, *mode)))
+        .collect();
+    if let Some(indices) = matches.indices_of("types") {
+        for (i, mode) in indices.zip(&args.types) {
+            let mode = match (mode, args.precision) {
+                (Mode::Float(size, _), Some(precision)) => {
+                    Mode::Float(*size, usize::from(precision))
+                }
+                _ => *mode,
+            };
+            modes.push((i, mode));
+        }
     }
-    fmt_line
+    modes.sort_by_key(|(i, _)| *i);
+    modes.into_iter().map(|(_, mode)| mode).collect()
 }
 
-/// Function to print out each line
+/// Print an error to stderr
 ///
 /// Args:
-///   line - The line to hexdump
-///   address - The address to print on the left side
-///   option - The options passed in
-/// Return:
-///   true if the line is printed, false if line_len > READ_LEN
-fn print_bin(line: &mut [u8], address: usize, options: &HdOptions) -> bool {
-    let line_len = line.len();
-    if line_len > READ_LEN {
-        eprintln!("line_len > READ_LEN, quitting print_bin()");
-        return false;
+///   err - The error to print, each skipped input of HdError::Inputs is
+///         printed on its own line
+fn report(err: &HdError) {
+    match err {
+        HdError::Inputs(errors) => errors.iter().for_each(report),
+        // Stop quietly when the reader of the dump goes away, e.g. hd file | head
+        HdError::Write(source) if source.kind() == ErrorKind::BrokenPipe => {}
+        _ => eprintln!("hd: {}", err),
     }
+}
 
-    let mut new_line;
-    // -C canonical format with |ascii| printed to the right of the hex
-    if options.canonical {
-        new_line = format!("{:08x} ", address);
-        if line_len > 0 {
-            for (i, _) in (0..READ_LEN).enumerate() {
-                if i < line_len {
-                    let spaces = if i == 8 { "  " } else { " " };
-                    new_line = format! {"{}{}{:02x}", new_line, spaces, line[i]};
-                } else {
-                    let spaces = if i == 8 { "    " } else { "   " };
-                    new_line = format! {"{}{}", new_line, spaces};
-                }
-            }
-            new_line = format!("{}  |{}|", new_line, convert_to_string(line));
-            println!("{}", new_line);
-        } else {
-            println!("{}", new_line);
-        }
-    // -x two bytes hexadecimal
-    } else if options.two_bytes_hex && line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len && i + 1 < line_len {
-                let spaces = "    ";
-                new_line = format! {"{}{}{:02x}{:02x}", new_line, spaces, line[i + 1], line[i]};
-            } else {
-                let spaces = if i == 8 { "   " } else { "  " };
-                new_line = format! {"{}{}", new_line, spaces};
-            }
-            i += 2;
-        }
-        println!("{}", new_line);
-    // -b one byte octal
-    } else if options.one_byte_octal && line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len {
-                let spaces = " ";
-                new_line = format! {"{}{}{:03o}", new_line, spaces, line[i]};
-            } else {
-                let spaces = if i == 8 { "   " } else { "  " };
-                new_line = format! {"{}{}", new_line, spaces};
-            }
-            i += 1;
-        }
-        println!("{}", new_line);
-    // -o two bytes octal
-    } else if options.two_bytes_octal && line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len && i + 1 < line_len {
-                let spaces = "  ";
-                let val: u16 = (line[i + 1] as u16) << 8 & 0xFF00 | line[i] as u16;
-                new_line = format! {"{}{}{:06o}", new_line, spaces, val};
-            } else {
-                let spaces = if i == 8 { "   " } else { "  " };
-                new_line = format! {"{}{}", new_line, spaces};
-            }
-            i += 2;
-        }
-        println!("{}", new_line);
-    // -d two bytes decimal
-    } else if options.two_bytes_dec && line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len && i + 1 < line_len {
-                let spaces = "   ";
-                let val: u16 = (line[i + 1] as u16) << 8 & 0xFF00 | line[i] as u16;
-                new_line = format! {"{}{}{:05}", new_line, spaces, val};
-            } else {
-                let spaces = if i == 8 { "   " } else { "  " };
-                new_line = format! {"{}{}", new_line, spaces};
-            }
-            i += 2;
-        }
-        println!("{}", new_line);
-        // -c one byte char
-    } else if options.one_byte_char && line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len {
-                new_line = format_one_byte_char(&new_line, line, i);
-            }
-            i += 1;
-        }
-        println!("{}", new_line);
-        // Default print no format string provided
-    } else if line_len > 0 {
-        new_line = format!("{:07x}", address);
-        let mut i = 0;
-        while i < READ_LEN {
-            if i < line_len && i + 1 < line_len {
-                let spaces = " ";
-                new_line = format! {"{}{}{:02x}{:02x}", new_line, spaces, line[i + 1], line[i]};
-            } else {
-                let spaces = if i == 8 { "   " } else { "  " };
-                new_line = format! {"{}{}", new_line, spaces};
+/// Print the dump statistics to stderr
+///
+/// Args:
+///   summary - What the dump did
+///   elapsed - How long the dump took
+fn print_stats(summary: &DumpSummary, elapsed: Duration) {
+    let seconds = elapsed.as_secs_f64();
+    let throughput = if seconds > 0.0 {
+        summary.bytes_dumped as f64 / seconds / (1024.0 * 1024.0)
+    } else {
+        0.0
+    };
+    eprintln!("hd: bytes read: {}", summary.bytes_dumped);
+    eprintln!("hd: lines printed: {}", summary.lines_printed);
+    eprintln!("hd: lines squeezed: {}", summary.lines_squeezed);
+    eprintln!("hd: execution time: {:?}", elapsed);
+    eprintln!("hd: throughput: {:.2} MiB/s", throughput);
+}
+
+/// Run hd --xxd
+///
+/// Return:
+///   Result(()) if success, or Err(HdError) if the dump failed
+fn xxd_main() -> Result<(), HdError> {
+    let args = XxdArgs::parse();
+    let infile = args.infile.unwrap_or_else(|| "-".to_string());
+    if args.reverse {
+        let input = BufReader::new(MultiReader::new(&[infile]));
+        return match args.outfile.as_deref() {
+            None | Some("-") => reverse::reverse(input, io::stdout().lock(), args.plain),
+            // Like xxd -r, write into outfile without truncating it
+            Some(outfile) => {
+                let file = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(false)
+                    .open(outfile)
+                    .map_err(HdError::Write)?;
+                let writer = io::BufWriter::with_capacity(WRITE_BUF_LEN, file);
+                reverse::reverse_seekable(input, writer, args.plain)
             }
-            i += 2;
         }
-        println!("{}", new_line);
-    } else {
-        new_line = format!("{:07x}", address);
-        println!("{}", new_line);
+        .map(|_| ());
     }
-    true
+    let style = if args.include {
+        XxdStyle::Include
+    } else if args.plain {
+        XxdStyle::Plain
+    } else {
+        XxdStyle::Normal
+    };
+    let cols = args.cols.map_or(
+        match style {
+            XxdStyle::Normal => xxd::DEFAULT_COLS,
+            XxdStyle::Plain => xxd::DEFAULT_PLAIN_COLS,
+            XxdStyle::Include => xxd::DEFAULT_INCLUDE_COLS,
+        },
+        usize::from,
+    );
+    // Like xxd, -i only prints the C declarations around a named file
+    let name = match (style, args.name) {
+        (XxdStyle::Include, Some(name)) => Some(name),
+        (XxdStyle::Include, None) if infile != "-" => Some(xxd::c_name(&infile)),
+        _ => None,
+    };
+    let opt = DumpOptions {
+        skip: match args.seek {
+            Some(Offset::Start(seek) | Offset::End(seek)) => seek,
+            None => 0,
+        },
+        skip_from_end: matches!(args.seek, Some(Offset::End(_))),
+        length: args.length,
+        xxd: Some(XxdOptions {
+            style,
+            cols,
+            group: args.group,
+            upper: args.upper,
+            name,
+        }),
+        // auto never colors an outfile
+        color: match args.color.as_str() {
+            "always" => true,
+            "never" => false,
+            _ => matches!(args.outfile.as_deref(), None | Some("-")) && color::auto(),
+        },
+        ..Default::default()
+    };
+    let writer: Box<dyn Write> = match args.outfile.as_deref() {
+        None | Some("-") => Box::new(io::stdout().lock()),
+        Some(outfile) => Box::new(File::create(outfile).map_err(HdError::Write)?),
+    };
+    let writer = io::BufWriter::with_capacity(WRITE_BUF_LEN, writer);
+    dump_files(&[infile], &opt, writer).map(|_| ())
 }
 
-/// Open the file and print its content to stdout
+/// Work out whether --xxd is given as an option
+///
+/// Only the arguments before a "--" count, and not the value of an option
+/// that takes one, so a file or an -e format string named --xxd stays one.
 ///
 /// Args:
-///   file - The file to hexdump
-///   req_bytes_to_dump - number of bytes to dump
-///   offset - The offset to start dumping at
-///   option - The format/nubmer options passed in
+///   args - The command line arguments, without the program name
 /// Return:
-///   Result(bytes_dumped) if succes, or Err(error string)
-fn hexdump(
-    file: String,
-    req_bytes_to_dump: usize,
-    offset: usize,
-    options: &HdOptions,
-) -> io::Result<usize> {
-    let path = Path::new(&file);
-    if !path.exists() {
-        let custom_error = Error::new(ErrorKind::Other, "Path does not exist, exiting");
-        eprintln!("Path does not exist, exiting",);
-        return Err(custom_error);
-    }
-    let mut f = File::open(&file)?;
-
-    let mut line: [u8; READ_LEN] = [0; READ_LEN];
-    let mut _prev_line: [u8; READ_LEN] = [0; READ_LEN];
-    let mut _file_length: usize = std::fs::metadata(file)?.len().try_into().unwrap();
-    let mut bytes_left_to_dump: usize = req_bytes_to_dump;
-    let mut address: usize = offset;
-
-    if offset > _file_length {
-        let custom_error = Error::new(ErrorKind::Other, "offset > _file_length, exiting");
-        eprintln!(
-            "offset >= _file_length ({} > {}), exiting",
-            offset, _file_length
-        );
-        return Err(custom_error);
-    }
-
-    if bytes_left_to_dump == 0 {
-        return Ok(0);
-    }
-
-    if bytes_left_to_dump + offset > _file_length {
-        bytes_left_to_dump = _file_length - offset;
-    }
-
-    if address != 0x0 {
-        f.seek(SeekFrom::Start(address.try_into().unwrap()))?;
-    }
-
-    let mut _is_skip_line_printed = false;
-    loop {
-        // Read a subline where bytes_left_to_dump is less than READ_LEN
-        if bytes_left_to_dump < READ_LEN {
-            let mut var_len_line: Vec<u8> = vec![0; bytes_left_to_dump];
-            f.read_exact(&mut var_len_line).unwrap_or_else(|_| {
-                panic!(
-                    "Didn't read {} bytes read {} bytes",
-                    bytes_left_to_dump,
-                    var_len_line.len()
-                )
-            });
-            let status = print_bin(&mut var_len_line, address, options);
-            if !status {
-                let custom_error = Error::new(ErrorKind::Other, "print_bin failed, exiting");
-                eprintln!("print_bin failed, exiting",);
-                return Err(custom_error);
-            }
-            address += bytes_left_to_dump;
-            break;
-        // Normal case, read a full READ_LEN line
+///   true if hd should take the xxd command line
+fn xxd_requested<I: IntoIterator<Item = OsString>>(args: I) -> bool {
+    let mut command = Args::command();
+    command.build();
+    // The options that take their value from the next argument
+    let options: Vec<&clap::Arg> = command
+        .get_arguments()
+        .filter(|arg| {
+            !arg.is_positional()
+                && !arg.is_require_equals_set()
+                && arg
+                    .get_num_args()
+                    .is_some_and(|range| range.min_values() > 0)
+        })
+        .collect();
+    let longs: Vec<&str> = options
+        .iter()
+        .flat_map(|arg| {
+            let aliases = arg.get_all_aliases().unwrap_or_default();
+            arg.get_long().into_iter().chain(aliases)
+        })
+        .collect();
+    let shorts: Vec<char> = options
+        .iter()
+        .flat_map(|arg| {
+            let aliases = arg.get_all_short_aliases().unwrap_or_default();
+            arg.get_short().into_iter().chain(aliases)
+        })
+        .collect();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if arg == "--" {
+            return false;
+        }
+        if arg == "--xxd" {
+            return true;
+        }
+        let value_next = if let Some(long) = arg.strip_prefix("--") {
+            longs.contains(&long)
+        } else if let Some(cluster) = arg.strip_prefix('-') {
+            // The first short option that takes a value takes the rest of
+            // the cluster, or the next argument if it is the last one
+            cluster
+                .char_indices()
+                .find(|(_, c)| shorts.contains(c))
+                .is_some_and(|(i, c)| i + c.len_utf8() == cluster.len())
         } else {
-            f.read_exact(&mut line).unwrap_or_else(|_| {
-                panic!(
-                    "{}",
-                    &format!("Didn't read {} bytes on line {:?}", READ_LEN, line).to_owned()
-                )
-            });
-            if options.no_squeezing {
-                let status = print_bin(&mut line, address, options);
-                if !status {
-                    let custom_error = Error::new(ErrorKind::Other, "print_bin failed, exiting");
-                    eprintln!("print_bin failed, exiting",);
-                    return Err(custom_error);
-                }
-            } else {
-                let _is_line_same = vecs_match(&line, &_prev_line);
-                if _is_line_same && !_is_skip_line_printed {
-                    println!("*");
-                    _is_skip_line_printed = true;
-                // This line matched the previous line so skip printing.
-                } else if _is_line_same && _is_skip_line_printed {
-                } else {
-                    let status = print_bin(&mut line, address, options);
-                    if !status {
-                        let custom_error =
-                            Error::new(ErrorKind::Other, "print_bin failed, exiting");
-                        eprintln!("print_bin failed, exiting",);
-                        return Err(custom_error);
-                    }
-                    _is_skip_line_printed = false;
-                }
-                for (i, item) in line.iter().enumerate() {
-                    _prev_line[i] = *item;
-                }
-            }
-            address += READ_LEN;
-            bytes_left_to_dump -= READ_LEN;
+            false
+        };
+        if value_next {
+            args.next();
         }
     }
-    // Return the bytes dumped wrapped in a Result
-    Ok(address - offset)
+    false
 }
 
 fn main() {
+    if xxd_requested(std::env::args_os().skip(1)) {
+        if let Err(err) = xxd_main() {
+            report(&err);
+            std::process::exit(err.exit_code());
+        }
+        return;
+    }
     let now = Instant::now();
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    if args.reverse {
+        let mut files = args.files.clone();
+        if files.is_empty() {
+            files.push("-".to_string());
+        }
+        let mut input = BufReader::new(MultiReader::new(&files));
+        let stdout = io::BufWriter::with_capacity(WRITE_BUF_LEN, io::stdout().lock());
+        let result = reverse::reverse(&mut input, stdout, false);
+        let errors = std::mem::take(&mut input.get_mut().errors);
+        let result = match result {
+            Ok(_) if !errors.is_empty() => Err(HdError::Inputs(errors)),
+            result => result,
+        };
+        if let Err(err) = result {
+            report(&err);
+            std::process::exit(err.exit_code());
+        }
+        return;
+    }
     let mut length: usize = 0;
-    let mut _file_length: usize = 0;
     let mut bytes_to_skip: usize = 0;
+    let skip_from_end = matches!(args.skip, Some(Offset::End(_)));
 
     if let Some(in_length) = args.length {
-        println!("Value for length:{}", in_length);
+        if args.stats {
+            eprintln!("hd: length: {}", in_length);
+        }
         length = in_length;
     }
-    if let Some(in_offset) = args.skip {
-        println!("Value for skip offset:{}", in_offset);
+    if let Some(Offset::Start(in_offset) | Offset::End(in_offset)) = args.skip {
+        if args.stats {
+            let sign = if skip_from_end { "-" } else { "" };
+            eprintln!("hd: skip offset: {}{}", sign, in_offset);
+        }
         bytes_to_skip = in_offset;
     }
-    let opt: HdOptions = HdOptions {
-        canonical: args.canonical,
-        one_byte_char: args.one_byte_char,
-        one_byte_octal: args.one_byte_octal,
-        no_squeezing: args.no_squeezing,
-        two_bytes_dec: args.two_bytes_dec,
-        two_bytes_octal: args.two_bytes_octal,
-        two_bytes_hex: args.two_bytes_hex,
+    let formats = match parse_formats(&args) {
+        Ok(formats) => formats,
+        Err(err) => {
+            report(&err);
+            std::process::exit(err.exit_code());
+        }
     };
-
-    // Check that the file exists before we try and open it.
-    let metadata = std::fs::metadata(&args.file);
-    let _result;
-    match metadata {
-        Ok(metadata) => {
-            // TODO: Fix this .unwrap() replace with '?'
-            _file_length = metadata.len().try_into().unwrap();
-            if length == 0 {
-                length = _file_length
-            } else if length > _file_length {
-                length = _file_length;
-            }
-            _result = hexdump(args.file, length, bytes_to_skip, &opt);
+    let endian = match args.endian.as_str() {
+        "big" => Endian::Big,
+        "native" => Endian::native(),
+        _ => Endian::Little,
+    };
+    let color = match args.color.as_str() {
+        "always" => true,
+        "never" => false,
+        _ => color::auto(),
+    };
+    if args.diff {
+        if skip_from_end {
+            usage_error("--diff can't start -s bytes before the end");
+        }
+        diff_main(&args, length, bytes_to_skip, color);
+    }
+    let find = args.find.as_deref().map(|text| {
+        let pattern = match args.find_as.as_str() {
+            "ascii" => Ok(Pattern::text(text)),
+            "utf16" => Ok(Pattern::utf16(text, endian)),
+            _ => Pattern::hex(text),
+        };
+        match pattern {
+            Ok(pattern) if !pattern.is_empty() => FindOptions {
+                pattern,
+                context: args.context,
+                list: args.list,
+                highlight: color,
+            },
+            Ok(_) => usage_error("the --find pattern is empty"),
+            Err(message) => usage_error(&message),
         }
-        Err(_) => {
-            eprintln!("file does not exist, exiting.");
+    });
+    // The display flags are passed as modes so they keep their order
+    let opt: DumpOptions = DumpOptions {
+        no_squeezing: args.no_squeezing,
+        formats,
+        skip: bytes_to_skip,
+        skip_from_end,
+        ranges: args.ranges.clone(),
+        // Dump everything up to the end of the input when no length is given
+        length: if length == 0 { None } else { Some(length) },
+        xxd: None,
+        width: args.width.map(usize::from),
+        group: args
+            .group
+            .as_deref()
+            .map(|g| g.parse().expect("checked by clap")),
+        endian,
+        modes: display_modes(&args, &matches),
+        color,
+        find,
+        strings: args.strings.map(|min_len| StringsOptions {
+            min_len,
+            encoding: match args.encoding.as_str() {
+                "utf8" => Encoding::Utf8,
+                "utf16le" => Encoding::Utf16Le,
+                "utf16be" => Encoding::Utf16Be,
+                _ => Encoding::Ascii,
+            },
+            decimal: args.radix == "dec",
+        }),
+        entropy: (args.entropy || args.histogram).then_some(EntropyOptions {
+            entropy: args.entropy,
+            histogram: args.histogram,
+            block_size: args.block_size,
+        }),
+        ..Default::default()
+    };
+    let width = opt.line_width();
+    for mode in opt.modes() {
+        let size = mode.group_size(opt.group);
+        if width % size != 0 {
+            usage_error(&format!("the width must be a multiple of {}", size));
         }
     }
-    if args.no_squeezing {
-        println!("Execution time: {:#?}", now.elapsed());
+
+    let mut files = args.files.clone();
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    let in_order = args.ranges.windows(2).all(|pair| {
+        pair[0]
+            .start
+            .saturating_add(pair[0].length.unwrap_or(usize::MAX))
+            <= pair[1].start
+    });
+    if !in_order && input::total_length(&files).is_none() {
+        usage_error("--range regions of standard input or a pipe must be in order");
+    }
+    let stdout = io::BufWriter::with_capacity(WRITE_BUF_LEN, io::stdout().lock());
+    let result = dump_files(&files, &opt, stdout);
+    if let (true, Ok(summary)) = (args.stats, &result) {
+        print_stats(summary, now.elapsed());
+    }
+    if let Err(err) = result {
+        report(&err);
+        std::process::exit(err.exit_code());
     }
 }
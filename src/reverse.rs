@@ -0,0 +1,318 @@
+//! Reverse mode, rebuild the binary from a dump
+//!
+//! Understands the canonical (-C) and default hd dumps, xxd dumps and plain
+//! hex dumps (xxd -p).  Lines are written at their address, a '*' squeeze
+//! marker repeats the line before it up to the next address and an address
+//! line on its own gives the end of the data.
+use crate::error::HdError;
+use std::io;
+use std::io::{BufRead, Seek, SeekFrom, Write};
+
+/// One line of a dump
+#[derive(Debug, PartialEq)]
+enum Line {
+    /// Bytes, at the given address or following on from the line before
+    Data(Option<u64>, Vec<u8>),
+    /// '*', the line before is repeated up to the next address
+    Squeeze,
+    /// An address with no data, the end of the dump
+    End(u64),
+}
+
+/// Where the rebuilt bytes are written
+trait Output {
+    /// Write bytes at the current position
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Write count zeros at the current position
+    fn zeros(&mut self, count: u64) -> io::Result<()> {
+        const ZEROS: [u8; 4096] = [0; 4096];
+        let mut left = count;
+        while left > 0 {
+            let n = left.min(ZEROS.len() as u64) as usize;
+            self.put(&ZEROS[..n])?;
+            left -= n as u64;
+        }
+        Ok(())
+    }
+
+    /// Move from position `from` to `to` when there's a gap in the addresses
+    ///
+    /// Return:
+    ///   Ok(false) if the output can't move there
+    fn jump(&mut self, from: u64, to: u64) -> io::Result<bool>;
+}
+
+/// Output that can't seek, gaps are filled with zeros
+struct Fill<W: Write>(W);
+
+impl<W: Write> Output for Fill<W> {
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)
+    }
+
+    fn jump(&mut self, from: u64, to: u64) -> io::Result<bool> {
+        if to < from {
+            return Ok(false);
+        }
+        self.zeros(to - from)?;
+        Ok(true)
+    }
+}
+
+/// Output that can seek, gaps are seeked over and addresses may go backwards
+struct Seeking<W: Write + Seek>(W);
+
+impl<W: Write + Seek> Output for Seeking<W> {
+    fn put(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)
+    }
+
+    fn jump(&mut self, _from: u64, to: u64) -> io::Result<bool> {
+        self.0.seek(SeekFrom::Start(to))?;
+        Ok(true)
+    }
+}
+
+/// Rebuilds the binary line by line
+struct Rebuilder<O: Output> {
+    out: O,
+    /// Address just past the bytes written so far
+    pos: u64,
+    /// The last line, held back so a smaller address after it can trim it
+    pending: Vec<u8>,
+    /// Set by '*', the line to repeat up to the next address
+    repeat: Option<Vec<u8>>,
+    /// Line number, for errors
+    line_number: usize,
+}
+
+impl<O: Output> Rebuilder<O> {
+    fn error(&self, message: &str) -> HdError {
+        HdError::BadDump {
+            line: self.line_number,
+            message: message.to_string(),
+        }
+    }
+
+    /// Write out the pending line and move to address
+    ///
+    /// Args:
+    ///   address - Where the next line starts
+    ///   fill - Zero fill a gap even if the output can seek, for the end
+    fn move_to(&mut self, address: u64, fill: bool) -> Result<(), HdError> {
+        // An address inside the pending line cuts it short, like the final
+        // word of a default dump that has an odd number of bytes
+        let pending_end = self.pos + self.pending.len() as u64;
+        if address >= self.pos && address < pending_end {
+            self.pending.truncate((address - self.pos) as usize);
+        }
+        self.out.put(&self.pending).map_err(HdError::Write)?;
+        self.pos += self.pending.len() as u64;
+        self.pending.clear();
+
+        if let Some(line) = self.repeat.take() {
+            if address > self.pos && !line.is_empty() {
+                let mut left = address - self.pos;
+                while left > 0 {
+                    let n = left.min(line.len() as u64) as usize;
+                    self.out.put(&line[..n]).map_err(HdError::Write)?;
+                    left -= n as u64;
+                }
+                self.pos = address;
+            }
+        }
+        if address != self.pos {
+            let moved = if fill && address > self.pos {
+                self.out.zeros(address - self.pos).map_err(HdError::Write)?;
+                true
+            } else {
+                self.out.jump(self.pos, address).map_err(HdError::Write)?
+            };
+            if !moved {
+                return Err(self.error(&format!(
+                    "address {:x} is before the end of the data, {:x}",
+                    address, self.pos
+                )));
+            }
+            self.pos = address;
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, line: Line) -> Result<(), HdError> {
+        match line {
+            Line::Data(address, bytes) => {
+                let address = address.unwrap_or(self.pos + self.pending.len() as u64);
+                self.move_to(address, false)?;
+                self.pending = bytes;
+            }
+            Line::Squeeze => {
+                if self.pending.is_empty() {
+                    return Err(self.error("'*' without a line before it"));
+                }
+                self.repeat = Some(self.pending.clone());
+            }
+            Line::End(address) => self.move_to(address, true)?,
+        }
+        Ok(())
+    }
+}
+
+/// Parse a hexadecimal address
+fn parse_address(token: &str) -> Option<u64> {
+    u64::from_str_radix(token, 16).ok()
+}
+
+/// Parse a run of hex digit pairs into bytes, in order
+fn parse_hex(token: &str, bytes: &mut Vec<u8>) -> Option<()> {
+    if token.len() % 2 != 0 {
+        return None;
+    }
+    for i in (0..token.len()).step_by(2) {
+        bytes.push(u8::from_str_radix(token.get(i..i + 2)?, 16).ok()?);
+    }
+    Some(())
+}
+
+/// Parse a line of an hd or xxd dump
+///
+/// Args:
+///   text - The line, without the newline
+/// Return:
+///   The Line, or None if it can't be parsed
+fn parse_line(text: &str) -> Option<Line> {
+    let text = text.trim_end();
+    if text == "*" {
+        return Some(Line::Squeeze);
+    }
+    let (address, rest) = text
+        .split_once(|c: char| c.is_ascii_whitespace())
+        .unwrap_or((text, ""));
+    let mut bytes = Vec::new();
+    if let Some(address) = address.strip_suffix(':') {
+        // xxd, groups of bytes in order up to the two spaces before the ASCII
+        let address = parse_address(address)?;
+        let hex = rest.split("  ").next().unwrap_or("");
+        for group in hex.split_ascii_whitespace() {
+            parse_hex(group, &mut bytes)?;
+        }
+        return Some(Line::Data(Some(address), bytes));
+    }
+    // hd, bytes (-C) or little-endian two-byte words (default and -x) up to
+    // the ASCII column
+    let address = parse_address(address)?;
+    let hex = rest.split('|').next().unwrap_or("");
+    for token in hex.split_ascii_whitespace() {
+        match token.len() {
+            2 => parse_hex(token, &mut bytes)?,
+            4 => {
+                let word = u16::from_str_radix(token, 16).ok()?;
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+            _ => return None,
+        }
+    }
+    if bytes.is_empty() {
+        Some(Line::End(address))
+    } else {
+        Some(Line::Data(Some(address), bytes))
+    }
+}
+
+/// Parse a line of a plain hex dump, hex digits that may be split by spaces
+fn parse_plain_line(text: &str) -> Option<Line> {
+    let digits: String = text.split_ascii_whitespace().collect();
+    let mut bytes = Vec::new();
+    parse_hex(&digits, &mut bytes)?;
+    Some(Line::Data(None, bytes))
+}
+
+/// Rebuild the binary from the dump in reader
+fn rebuild<R: BufRead, O: Output>(mut reader: R, out: O, plain: bool) -> Result<u64, HdError> {
+    let mut rebuilder = Rebuilder {
+        out,
+        pos: 0,
+        pending: Vec::new(),
+        repeat: None,
+        line_number: 0,
+    };
+    // None until the first line with something on it shows the kind of dump
+    let mut plain = plain.then_some(true);
+    let mut raw = Vec::new();
+    loop {
+        raw.clear();
+        let read = reader
+            .read_until(b'\n', &mut raw)
+            .map_err(|source| HdError::Read { path: None, source })?;
+        if read == 0 {
+            break;
+        }
+        rebuilder.line_number += 1;
+        let text = String::from_utf8_lossy(&raw);
+        if text.trim().is_empty() {
+            continue;
+        }
+        // A first line that isn't a line of an hd or xxd dump can only be
+        // plain hex, an address on its own is still the end of a dump
+        let line = match plain {
+            Some(true) => parse_plain_line(&text),
+            Some(false) => parse_line(&text),
+            None => {
+                let line = parse_line(&text);
+                plain = Some(line.is_none());
+                line.or_else(|| parse_plain_line(&text))
+            }
+        };
+        match line {
+            Some(line) => rebuilder.line(line)?,
+            None => return Err(rebuilder.error("not a line of a hex dump")),
+        }
+    }
+    let end = rebuilder.pos + rebuilder.pending.len() as u64;
+    rebuilder.move_to(end, true)?;
+    Ok(rebuilder.pos)
+}
+
+/// Rebuild the binary from a dump, zero filling any gaps in the addresses
+///
+/// Args:
+///   reader - The dump
+///   writer - The sink the binary is written to
+///   plain - The dump is plain hex digits, otherwise the kind of dump is
+///           worked out from its first line
+/// Return:
+///   Result(length) of the binary, or Err(HdError) if the dump can't be parsed,
+///   an address goes backwards, or reading or writing fails
+pub fn reverse<R: BufRead, W: Write>(
+    reader: R,
+    mut writer: W,
+    plain: bool,
+) -> Result<u64, HdError> {
+    let length = rebuild(reader, Fill(&mut writer), plain)?;
+    writer.flush().map_err(HdError::Write)?;
+    Ok(length)
+}
+
+/// Rebuild the binary from a dump into an output that can seek
+///
+/// Gaps in the addresses are seeked over, and an address that goes backwards
+/// overwrites what is already there, so a dump of a few lines patches writer.
+///
+/// Args:
+///   reader - The dump
+///   writer - The sink the binary is written to, starting at position 0
+///   plain - The dump is plain hex digits, otherwise the kind of dump is
+///           worked out from its first line
+/// Return:
+///   Result(end) the position after the last byte written, or Err(HdError)
+///   if the dump can't be parsed or reading or writing fails
+pub fn reverse_seekable<R: BufRead, W: Write + Seek>(
+    reader: R,
+    mut writer: W,
+    plain: bool,
+) -> Result<u64, HdError> {
+    let end = rebuild(reader, Seeking(&mut writer), plain)?;
+    writer.flush().map_err(HdError::Write)?;
+    Ok(end)
+}
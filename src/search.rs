@@ -0,0 +1,305 @@
+//! --find, search the input for a byte pattern
+//!
+//! The input is searched as one stream, so matches that span the lines of
+//! the dump or the reads from the input are found too.  The matches are
+//! either listed by offset or shown as the lines of the dump around them.
+use crate::error::HdError;
+use crate::format::{Endian, Format};
+use crate::{DumpSummary, READ_BUF_LEN, read_error};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+
+/// A byte pattern, None matches any byte
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern(Vec<Option<u8>>);
+
+impl Pattern {
+    /// Parse hex bytes with ?? for any byte, e.g. "7f 45 4c 46" or "cafe??be"
+    ///
+    /// Args:
+    ///   text - Pairs of hex digits, spaces between them are ignored
+    /// Return:
+    ///   Result(Pattern), or Err(message) if text isn't pairs of hex digits
+    pub fn hex(text: &str) -> Result<Pattern, String> {
+        let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if digits.is_empty() {
+            return Err("the pattern is empty".to_string());
+        }
+        if digits.len() % 2 != 0 {
+            return Err(format!("'{}' is not a whole number of bytes", text));
+        }
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| match pair {
+                b"??" => Ok(None),
+                _ => std::str::from_utf8(pair)
+                    .ok()
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .map(Some)
+                    .ok_or_else(|| {
+                        format!(
+                            "'{}' is not a hex byte or ??",
+                            String::from_utf8_lossy(pair)
+                        )
+                    }),
+            })
+            .collect::<Result<Vec<Option<u8>>, String>>()?;
+        Ok(Pattern(bytes))
+    }
+
+    /// The bytes of a string
+    pub fn text(text: &str) -> Pattern {
+        Pattern(text.bytes().map(Some).collect())
+    }
+
+    /// The UTF-16 code units of a string
+    ///
+    /// Args:
+    ///   text - The string to search for
+    ///   endian - The byte order of the code units
+    pub fn utf16(text: &str, endian: Endian) -> Pattern {
+        let bytes = text
+            .encode_utf16()
+            .flat_map(|unit| match endian {
+                Endian::Little => unit.to_le_bytes(),
+                Endian::Big => unit.to_be_bytes(),
+            })
+            .map(Some)
+            .collect();
+        Pattern(bytes)
+    }
+
+    /// Number of bytes the pattern matches
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// true if the pattern matches nothing
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// true if bytes start with a match of the pattern
+    fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.0.len()
+            && self
+                .0
+                .iter()
+                .zip(bytes)
+                .all(|(want, byte)| want.is_none_or(|want| want == *byte))
+    }
+}
+
+/// Options for --find
+#[derive(Clone, Debug, PartialEq)]
+pub struct FindOptions {
+    /// The pattern to search for
+    pub pattern: Pattern,
+    /// --context lines of the dump shown before and after each match
+    pub context: usize,
+    /// --list print the offset of each match instead of the dump around it
+    pub list: bool,
+    /// Highlight the matches in the dump in color, otherwise they are marked
+    /// with a line of carets under them
+    pub highlight: bool,
+}
+
+/// The input, read in chunks and searched as one stream
+struct Scanner<'a, R: Read> {
+    reader: R,
+    pattern: &'a Pattern,
+    /// The bytes from position base of the input on
+    data: Vec<u8>,
+    base: usize,
+    /// Position of the next possible match to test
+    scanned: usize,
+    /// Bytes still to read, for -n
+    left: usize,
+    eof: bool,
+}
+
+impl<R: Read> Scanner<'_, R> {
+    /// Position just past the bytes read so far
+    fn end(&self) -> usize {
+        self.base + self.data.len()
+    }
+
+    /// Read the next chunk of input and search it
+    ///
+    /// Args:
+    ///   found - The start of every new match is pushed onto it
+    fn fill(&mut self, found: &mut VecDeque<usize>) -> Result<(), HdError> {
+        let old_len = self.data.len();
+        let want = READ_BUF_LEN.min(self.left);
+        self.data.resize(old_len + want, 0);
+        let bytes_read =
+            crate::read_full(&mut self.reader, &mut self.data[old_len..]).map_err(read_error)?;
+        self.data.truncate(old_len + bytes_read);
+        self.left -= bytes_read;
+        self.eof = bytes_read < want || self.left == 0;
+        while self.scanned + self.pattern.len() <= self.end() {
+            if self.pattern.matches(&self.data[self.scanned - self.base..]) {
+                found.push_back(self.scanned);
+            }
+            self.scanned += 1;
+        }
+        Ok(())
+    }
+
+    /// Forget the bytes before position, they are no longer needed
+    fn discard(&mut self, position: usize) {
+        let position = position.min(self.scanned);
+        if position > self.base {
+            self.data.drain(..position - self.base);
+            self.base = position;
+        }
+    }
+}
+
+/// Search the input and print the offset of every match, one per line
+///
+/// Args:
+///   options - The pattern to search for
+///   reader - The input, already positioned at the first byte to search
+///   writer - The sink the offsets are written to
+///   offset - The address of the first byte read from reader
+///   length - Number of bytes to search, None for everything up to the end
+/// Return:
+///   Result(DumpSummary) with a line printed per match, or Err(HdError) if
+///   reading or writing fails
+pub fn list_matches<R: Read, W: Write>(
+    options: &FindOptions,
+    reader: R,
+    mut writer: W,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<DumpSummary, HdError> {
+    let mut scanner = Scanner {
+        reader,
+        pattern: &options.pattern,
+        data: Vec::new(),
+        base: 0,
+        scanned: 0,
+        left: length.unwrap_or(usize::MAX),
+        eof: false,
+    };
+    let mut found = VecDeque::new();
+    let mut lines_printed = 0;
+    while !scanner.eof {
+        scanner.fill(&mut found)?;
+        for start in found.drain(..) {
+            writeln!(writer, "{:08x}", offset + start).map_err(HdError::Write)?;
+            lines_printed += 1;
+        }
+        scanner.discard(scanner.scanned);
+    }
+    writer.flush().map_err(HdError::Write)?;
+    Ok(DumpSummary {
+        bytes_dumped: scanner.end(),
+        end_address: offset + scanner.end(),
+        lines_printed,
+        lines_squeezed: 0,
+    })
+}
+
+/// Search the input and print the lines of the dump around every match
+///
+/// Lines that are more than options.context lines away from a match are left
+/// out, and "--" is printed where lines were left out between two matches.
+/// Without options.highlight a line of ^ under each line marks the matches.
+///
+/// Args:
+///   options - The pattern to search for and the lines to show around it
+///   format - The format of the dump
+///   reader - The input, already positioned at the first byte to search
+///   writer - The sink the dump is written to
+///   offset - The address of the first byte read from reader
+///   length - Number of bytes to search, None for everything up to the end
+/// Return:
+///   Result(DumpSummary) if success, or Err(HdError) if reading or writing
+///   fails
+pub fn dump_matches<R: Read, W: Write>(
+    options: &FindOptions,
+    format: &Format,
+    reader: R,
+    mut writer: W,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<DumpSummary, HdError> {
+    let pattern_len = options.pattern.len();
+    let block_size = format.block_size();
+    let context = options.context;
+    let mut scanner = Scanner {
+        reader,
+        pattern: &options.pattern,
+        data: Vec::new(),
+        base: 0,
+        scanned: 0,
+        left: length.unwrap_or(usize::MAX),
+        eof: false,
+    };
+    // Starts of the matches that can still touch a line to come
+    let mut found: VecDeque<usize> = VecDeque::new();
+    let mut text: Vec<u8> = Vec::new();
+    let mut marked: Vec<bool> = Vec::new();
+    let mut next_line: usize = 0;
+    let mut last_printed: Option<usize> = None;
+    let mut lines_printed = 0;
+    loop {
+        scanner.fill(&mut found)?;
+        // A line can be printed once every match within context lines after
+        // it has been searched for
+        while next_line * block_size < scanner.end()
+            && (scanner.eof
+                || scanner.end() >= (next_line + context + 1) * block_size + pattern_len - 1)
+        {
+            let line = next_line;
+            next_line += 1;
+            while found
+                .front()
+                .is_some_and(|start| (start + pattern_len - 1) / block_size + context < line)
+            {
+                found.pop_front();
+            }
+            let near = found
+                .front()
+                .is_some_and(|start| start / block_size <= line + context);
+            if !near {
+                continue;
+            }
+            let start = line * block_size;
+            let end = scanner.end().min(start + block_size);
+            let block = &scanner.data[start - scanner.base..end - scanner.base];
+            marked.clear();
+            marked.extend((start..end).map(|position| {
+                found
+                    .iter()
+                    .take_while(|match_start| **match_start <= position)
+                    .any(|match_start| position < match_start + pattern_len)
+            }));
+            if last_printed.is_some_and(|last| last + 1 < line) {
+                writer.write_all(b"--\n").map_err(HdError::Write)?;
+            }
+            text.clear();
+            if options.highlight {
+                format.display_marked(&mut text, block, offset + start, &marked);
+            } else {
+                format.display_carets(&mut text, block, offset + start, &marked);
+            }
+            writer.write_all(&text).map_err(HdError::Write)?;
+            last_printed = Some(line);
+            lines_printed += 1;
+        }
+        if scanner.eof {
+            break;
+        }
+        scanner.discard(next_line * block_size);
+    }
+    writer.flush().map_err(HdError::Write)?;
+    Ok(DumpSummary {
+        bytes_dumped: scanner.end(),
+        end_address: offset + scanner.end(),
+        lines_printed,
+        lines_squeezed: 0,
+    })
+}
@@ -0,0 +1,139 @@
+//! Sizes, offsets and regions given on the command line, e.g. -n 4KiB,
+//! -s -64 or --range 0x200+64
+//!
+//! A size is a decimal number, a hex number after 0x or an octal number
+//! after a leading 0, followed by an optional suffix.  The digits of the
+//! number come first, so 0x1b is hex 1b and not 1 block of 512 bytes.
+
+/// The size suffixes and what they multiply by, u64 so that T fits on
+/// 32-bit targets too
+const SUFFIXES: [(&str, u64); 14] = [
+    ("", 1),
+    ("b", 512),
+    ("k", 1 << 10),
+    ("K", 1 << 10),
+    ("KiB", 1 << 10),
+    ("kB", 1000),
+    ("M", 1 << 20),
+    ("MiB", 1 << 20),
+    ("MB", 1000 * 1000),
+    ("G", 1 << 30),
+    ("GiB", 1 << 30),
+    ("GB", 1000 * 1000 * 1000),
+    ("T", 1 << 40),
+    ("TiB", 1 << 40),
+];
+
+/// Where -s starts the dump
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Offset {
+    /// Bytes from the start of the input
+    Start(usize),
+    /// Bytes back from the end of the input, -s with a minus sign
+    End(usize),
+}
+
+/// Parse a size, e.g. 4096, 0x1000, 010000, 4k, 4KiB or 8b
+///
+/// Args:
+///   text - The number with an optional suffix: b (512 bytes), k, K or KiB,
+///          M or MiB, G or GiB, T or TiB, or kB, MB and GB for powers of 1000
+/// Return:
+///   Result(bytes), or Err(message) if text isn't a size or is too big
+pub fn parse_size(text: &str) -> Result<usize, String> {
+    let hex = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X"));
+    // 0 on its own or before a suffix is still decimal zero
+    let octal = text
+        .strip_prefix('0')
+        .filter(|rest| !rest.is_empty() && !rest.starts_with(char::is_alphabetic));
+    let (radix, rest) = match (hex, octal) {
+        (Some(rest), _) => (16, rest),
+        (None, Some(rest)) => (8, rest),
+        (None, None) => (10, text),
+    };
+    let digits_len = rest
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or(rest.len());
+    let (digits, suffix) = rest.split_at(digits_len);
+    if digits.is_empty() {
+        return Err(match radix {
+            16 => format!("'{}' has no hex digits after 0x", text),
+            8 => format!("'{}' is not an octal number, a leading 0 means octal", text),
+            _ => format!("'{}' is not a number", text),
+        });
+    }
+    let multiplier = SUFFIXES
+        .iter()
+        .find(|(name, _)| *name == suffix)
+        .map(|(_, multiplier)| *multiplier)
+        .ok_or_else(|| {
+            format!(
+                "'{}' has an unknown suffix '{}', use b, k, KiB, M, MiB, G, GiB, T, TiB, kB, MB or GB",
+                text, suffix
+            )
+        })?;
+    let too_big = || format!("'{}' is too big, sizes go up to {}", text, usize::MAX);
+    u64::from_str_radix(digits, radix)
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .and_then(|value| usize::try_from(value).ok())
+        .ok_or_else(too_big)
+}
+
+/// Parse an -s offset, a size with a minus sign counts back from the end
+///
+/// Args:
+///   text - The size, e.g. 0x200 or -64, a plus sign is allowed too
+/// Return:
+///   Result(Offset), or Err(message) if text isn't a size or is too big
+pub fn parse_offset(text: &str) -> Result<Offset, String> {
+    if let Some(size) = text.strip_prefix('-') {
+        parse_size(size).map(Offset::End)
+    } else {
+        parse_size(text.strip_prefix('+').unwrap_or(text)).map(Offset::Start)
+    }
+}
+
+/// A --range region of the input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    /// Offset of the first byte of the region
+    pub start: usize,
+    /// Number of bytes in the region, None for everything up to the end
+    pub length: Option<usize>,
+}
+
+/// Parse a --range region, START:END or START+LEN
+///
+/// Args:
+///   text - Two sizes, e.g. 0x200:0x400 or 1MiB+512, END is the offset just
+///          past the region and may be left out for everything to the end
+/// Return:
+///   Result(Region), or Err(message) if text isn't a region or ends before
+///   it starts
+pub fn parse_range(text: &str) -> Result<Region, String> {
+    if let Some((start, length)) = text.split_once('+') {
+        return Ok(Region {
+            start: parse_size(start)?,
+            length: Some(parse_size(length)?),
+        });
+    }
+    let Some((start, end)) = text.split_once(':') else {
+        return Err(format!("'{}' is not START:END or START+LEN", text));
+    };
+    let start = parse_size(start)?;
+    if end.is_empty() {
+        return Ok(Region {
+            start,
+            length: None,
+        });
+    }
+    let end = parse_size(end)?;
+    if end < start {
+        return Err(format!("'{}' ends before it starts", text));
+    }
+    Ok(Region {
+        start,
+        length: Some(end - start),
+    })
+}
@@ -0,0 +1,251 @@
+//! --strings, print the runs of printable characters like strings(1)
+//!
+//! A character is printable when it is printable ASCII, the bytes
+//! printable_byte shows as themselves.  UTF-8 also takes the multi-byte
+//! characters that aren't control characters.  UTF-16 takes only the code
+//! units of printable ASCII, like strings -e l and -e b, so that noise isn't
+//! read as CJK text, and looks for them at even and odd offsets alike.
+use crate::error::HdError;
+use crate::{DumpSummary, READ_BUF_LEN, printable_byte, read_error, read_full};
+use std::io::{Read, Write};
+
+/// How the characters of the input are encoded
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// One byte per character, printable ASCII only
+    #[default]
+    Ascii,
+    /// ASCII and the multi-byte UTF-8 sequences of printable characters
+    Utf8,
+    /// Two bytes per character, least significant byte first
+    Utf16Le,
+    /// Two bytes per character, most significant byte first
+    Utf16Be,
+}
+
+/// Options for --strings
+#[derive(Clone, Debug, PartialEq)]
+pub struct StringsOptions {
+    /// Shortest run of printable characters printed
+    pub min_len: usize,
+    /// How the characters are encoded
+    pub encoding: Encoding,
+    /// --radix dec prints the offsets in decimal instead of hex
+    pub decimal: bool,
+}
+
+/// true if printable_byte shows byte as itself
+fn is_printable_ascii(byte: u8) -> bool {
+    printable_byte(byte) == byte
+}
+
+/// true if a character that isn't ASCII is printable
+fn is_printable_char(c: char) -> bool {
+    !c.is_control()
+}
+
+/// The run of printable characters being collected
+#[derive(Default)]
+struct Run {
+    /// Offset of the first character of the run
+    start: usize,
+    text: String,
+    chars: usize,
+    /// The runs that were long enough, with their offsets, not yet printed
+    found: Vec<(usize, String)>,
+}
+
+impl Run {
+    /// Add a character that starts at offset
+    fn push(&mut self, offset: usize, c: char) {
+        if self.chars == 0 {
+            self.start = offset;
+        }
+        self.text.push(c);
+        self.chars += 1;
+    }
+
+    /// End the run, keeping it if it has at least min_len characters
+    fn end(&mut self, min_len: usize) {
+        if self.chars >= min_len.max(1) {
+            self.found
+                .push((self.start, std::mem::take(&mut self.text)));
+        }
+        self.text.clear();
+        self.chars = 0;
+    }
+
+    /// Offset before which the run can't find anything more
+    ///
+    /// Args:
+    ///   next - The offset of the next character that could be pushed
+    fn next_start(&self, next: usize) -> usize {
+        if self.chars > 0 { self.start } else { next }
+    }
+}
+
+/// Print the runs found before an offset, in the order of their offsets
+///
+/// Args:
+///   runs - The runs, one for each alignment of UTF-16
+///   before - Runs that start at this offset or after it are kept for later
+///   decimal - Print the offsets in decimal instead of hex
+///   writer - The sink the strings are written to
+/// Return:
+///   Result(lines) the number of strings printed, or Err(HdError) if
+///   writing fails
+fn write_found<W: Write>(
+    runs: &mut [Run],
+    before: usize,
+    decimal: bool,
+    writer: &mut W,
+) -> Result<usize, HdError> {
+    let mut ready: Vec<(usize, String)> = Vec::new();
+    for run in runs.iter_mut() {
+        let count = run.found.partition_point(|(start, _)| *start < before);
+        ready.extend(run.found.drain(..count));
+    }
+    ready.sort_by_key(|(start, _)| *start);
+    for (start, text) in &ready {
+        let result = if decimal {
+            writeln!(writer, "{:08}  {}", start, text)
+        } else {
+            writeln!(writer, "{:08x}  {}", start, text)
+        };
+        result.map_err(HdError::Write)?;
+    }
+    Ok(ready.len())
+}
+
+/// Print the runs of printable characters in the input
+///
+/// Args:
+///   options - The shortest run, encoding and radix of the offsets
+///   reader - The input, already positioned at the first byte to scan
+///   writer - The sink the strings are written to
+///   offset - The address of the first byte read from reader
+///   length - Number of bytes to scan, None for everything up to the end
+/// Return:
+///   Result(DumpSummary) with a line printed per string, or Err(HdError) if
+///   reading or writing fails
+pub fn dump_strings<R: Read, W: Write>(
+    options: &StringsOptions,
+    mut reader: R,
+    mut writer: W,
+    offset: usize,
+    length: Option<usize>,
+) -> Result<DumpSummary, HdError> {
+    let min_len = options.min_len;
+    // UTF-16 keeps a run for the code units at even and at odd offsets from
+    // the start of the scan, the other encodings only use the first
+    let mut runs = [Run::default(), Run::default()];
+    let mut input = vec![0u8; READ_BUF_LEN];
+    let mut left = length.unwrap_or(usize::MAX);
+    let mut address = offset;
+    let mut printed = 0;
+    // The bytes of a UTF-8 sequence read so far and the offset of its first
+    // byte
+    let mut pending: Vec<u8> = Vec::with_capacity(4);
+    let mut pending_start = 0;
+    // The byte before, the first byte of a UTF-16 code unit
+    let mut previous: Option<u8> = None;
+    while left > 0 {
+        let want = READ_BUF_LEN.min(left);
+        let bytes_read = read_full(&mut reader, &mut input[..want]).map_err(read_error)?;
+        for &byte in &input[..bytes_read] {
+            let run = &mut runs[0];
+            match options.encoding {
+                Encoding::Ascii => {
+                    if is_printable_ascii(byte) {
+                        run.push(address, byte as char);
+                    } else {
+                        run.end(min_len);
+                    }
+                }
+                Encoding::Utf8 => {
+                    // A byte that can't continue the sequence ends it and is
+                    // then looked at on its own
+                    if !pending.is_empty() && !(0x80..=0xbf).contains(&byte) {
+                        pending.clear();
+                        run.end(min_len);
+                    }
+                    if pending.is_empty() {
+                        match byte {
+                            0x00..=0x7f if is_printable_ascii(byte) => {
+                                run.push(address, byte as char)
+                            }
+                            0xc2..=0xf4 => {
+                                pending.push(byte);
+                                pending_start = address;
+                            }
+                            _ => run.end(min_len),
+                        }
+                    } else {
+                        pending.push(byte);
+                        let needed = match pending[0] {
+                            0xc2..=0xdf => 2,
+                            0xe0..=0xef => 3,
+                            _ => 4,
+                        };
+                        if pending.len() == needed {
+                            match std::str::from_utf8(&pending)
+                                .ok()
+                                .and_then(|s| s.chars().next())
+                            {
+                                Some(c) if is_printable_char(c) => run.push(pending_start, c),
+                                _ => run.end(min_len),
+                            }
+                            pending.clear();
+                        }
+                    }
+                }
+                Encoding::Utf16Le | Encoding::Utf16Be => {
+                    // Every byte after the first ends a code unit, of the
+                    // alignment of the byte before it
+                    if let Some(first) = previous {
+                        let [high, low] = match options.encoding {
+                            Encoding::Utf16Le => [byte, first],
+                            _ => [first, byte],
+                        };
+                        let run = &mut runs[(address - 1 - offset) % 2];
+                        if high == 0 && is_printable_ascii(low) {
+                            run.push(address - 1, low as char);
+                        } else {
+                            run.end(min_len);
+                        }
+                    }
+                    previous = Some(byte);
+                }
+            }
+            address += 1;
+        }
+        // A character can still start at the bytes of a sequence or code
+        // unit that isn't finished yet
+        let next = match (pending.is_empty(), previous) {
+            (false, _) => pending_start,
+            (true, Some(_)) => address - 1,
+            (true, None) => address,
+        };
+        let before = runs
+            .iter()
+            .map(|run| run.next_start(next))
+            .min()
+            .unwrap_or(next);
+        printed += write_found(&mut runs, before, options.decimal, &mut writer)?;
+        left -= bytes_read;
+        if bytes_read < want {
+            break;
+        }
+    }
+    for run in runs.iter_mut() {
+        run.end(min_len);
+    }
+    printed += write_found(&mut runs, usize::MAX, options.decimal, &mut writer)?;
+    writer.flush().map_err(HdError::Write)?;
+    Ok(DumpSummary {
+        bytes_dumped: address - offset,
+        end_address: address,
+        lines_printed: printed,
+        lines_squeezed: 0,
+    })
+}
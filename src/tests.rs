@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::module_inception, clippy::bool_assert_comparison)]
 mod tests {
     #[allow(unused_imports)]
     use super::*;
@@ -52,12 +53,10 @@ mod tests {
 
     #[test]
     fn test_bad_file_path() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        #[allow(unused_imports)]
-        use crate::print_bin;
         let f1 = "test/test0_does_not_exist.bin".to_string();
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -65,18 +64,28 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
-        assert_eq!(hexdump(f1.clone(), 10, 10, &opt).is_err(), true);
+        assert_eq!(
+            hexdump(
+                &[f1],
+                &DumpOptions {
+                    length: Some(10),
+                    skip: 10,
+                    ..opt
+                },
+            )
+            .is_err(),
+            true
+        );
     }
 
     #[test]
     fn test_bad_file_path_one() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        #[allow(unused_imports)]
-        use crate::print_bin;
         let f1 = "test0.bin".to_string();
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -84,18 +93,28 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
-        assert_eq!(hexdump(f1.clone(), 10, 10, &opt).is_err(), true);
+        assert_eq!(
+            hexdump(
+                &[f1],
+                &DumpOptions {
+                    length: Some(10),
+                    skip: 10,
+                    ..opt
+                },
+            )
+            .is_err(),
+            true
+        );
     }
 
     #[test]
     fn test_hd_offset_gt_bytes_to_dump_one() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        #[allow(unused_imports)]
-        use crate::print_bin;
         let f1 = "test/test0.bin".to_string();
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -103,18 +122,26 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
 
-        let status = hexdump(f1.clone(), 10, 11, &opt);
+        let status = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(10),
+                skip: 11,
+                ..opt
+            },
+        );
         println!("status: {:?}", status);
         assert_eq!(status.is_ok(), true);
     }
     #[test]
     fn test_hd_offset_gt_bytes_to_dump_two() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
         let f1 = "test/test0.bin".to_string();
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -122,15 +149,26 @@ mod tests {
             two_bytes_dec: true,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
-        assert_eq!(hexdump(f1.clone(), 976, 0, &opt).is_ok(), true,);
+        assert_eq!(
+            hexdump(
+                &[f1],
+                &DumpOptions {
+                    length: Some(976),
+                    ..opt
+                },
+            )
+            .is_ok(),
+            true,
+        );
     }
     #[test]
     fn test_hd_offset_gt_bytes_to_dump_three() {
         use crate::hexdump;
         let f1 = "test/test0.bin".to_string();
-        use crate::HdOptions;
-        let opt: HdOptions = HdOptions {
+        use crate::DumpOptions;
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -138,15 +176,27 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
-        assert_eq!(hexdump(f1.clone(), 20, 2000, &opt).is_err(), true,);
+        assert_eq!(
+            hexdump(
+                &[f1],
+                &DumpOptions {
+                    length: Some(20),
+                    skip: 2000,
+                    ..opt
+                },
+            )
+            .is_err(),
+            true,
+        );
     }
     #[test]
     fn test_hd_offset_gt_bytes_to_dump_four() {
         use crate::hexdump;
         let f1 = "test/test0.bin".to_string();
-        use crate::HdOptions;
-        let opt: HdOptions = HdOptions {
+        use crate::DumpOptions;
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -154,16 +204,28 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: true,
+            ..Default::default()
         };
-        assert_eq!(hexdump(f1.clone(), 0, 0xa1, &opt).is_ok(), true,);
+        assert_eq!(
+            hexdump(
+                &[f1],
+                &DumpOptions {
+                    length: Some(0),
+                    skip: 0xa1,
+                    ..opt
+                },
+            )
+            .is_ok(),
+            true,
+        );
     }
 
     #[test]
     fn test_hd_bytes_to_dump_ten() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
         let f1 = "test/test0.bin".to_string();
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -171,21 +233,28 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let bytes_to_dump = 10;
         println!(
             "hd {} bytes, visually inspect and make sure only {} bytes are printed",
             bytes_to_dump, bytes_to_dump
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, 0, &opt.clone());
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
     #[test]
     fn test_hd_bytes_to_dump_twenty() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -193,6 +262,7 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 20;
@@ -200,7 +270,13 @@ mod tests {
             "hd {} bytes, visually inspect and make sure only {} bytes are printed",
             bytes_to_dump, bytes_to_dump
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, 0, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
@@ -209,8 +285,8 @@ mod tests {
     fn test_hd_bytes_to_dump_one() {
         use crate::hexdump;
         let f1 = "test/test0.bin".to_string();
-        use crate::HdOptions;
-        let opt: HdOptions = HdOptions {
+        use crate::DumpOptions;
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: true,
@@ -218,6 +294,7 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let bytes_to_dump = 1024;
         let offset = 0x0; // 0
@@ -231,15 +308,21 @@ mod tests {
             "hd {} bytes, file length {} make sure {} lines are skipped and {} bytes are dumped",
             bytes_to_dump, _file_length, offset, min_bytes
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, 0, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), min_bytes);
     }
     #[test]
     fn test_hd_bytes_to_dump_nine_ninty() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -247,6 +330,7 @@ mod tests {
             two_bytes_octal: false,
             two_bytes_dec: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 990;
@@ -256,16 +340,22 @@ mod tests {
             MAX_BYTES_TO_DUMP, MAX_BYTES_TO_DUMP
         );
         // Special case where we try and read past the end of the file
-        let result = hexdump(f1.clone(), bytes_to_dump, 0, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), MAX_BYTES_TO_DUMP);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_eight_hunderd_big_offset() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -273,6 +363,7 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let bytes_to_dump = 0x320; // 800
         let f2 = "test/test1.bin".to_string();
@@ -288,7 +379,14 @@ mod tests {
             bytes_to_dump, _file_length, offset, min_bytes
         );
         // Special case where we try and read past the end of the file
-        let result = hexdump(f2.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f2],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         // Only read until the end of file.
         assert_eq!(result.unwrap(), min_bytes);
@@ -296,9 +394,9 @@ mod tests {
 
     #[test]
     fn test_hd_no_squeezing() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -306,6 +404,7 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test1.bin".to_string();
         let bytes_to_dump = 10;
@@ -313,16 +412,22 @@ mod tests {
             "hd {} bytes, visually inspect and make sure only {} bytes are printed",
             bytes_to_dump, bytes_to_dump
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, 0, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_no_squeezing_two() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -330,6 +435,7 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: true,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test1.bin".to_string();
         let bytes_to_dump = 32;
@@ -338,16 +444,23 @@ mod tests {
             "hd {} bytes, visually inspect and make sure only {} bytes are printed",
             bytes_to_dump, bytes_to_dump
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_no_squeezing_three() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -355,6 +468,7 @@ mod tests {
             two_bytes_octal: false,
             two_bytes_dec: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test1.bin".to_string();
         let mut _file_length: usize = std::fs::metadata(&f1)
@@ -371,16 +485,23 @@ mod tests {
             "hd {} bytes, file length {} make sure {} lines are skipped and {} bytes are dumped",
             bytes_to_dump, _file_length, offset, min_bytes
         );
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), min_bytes);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_one() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: true,
@@ -388,21 +509,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 25;
         let offset = 0;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_two() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -410,21 +539,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 10;
         let offset = 5;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_three() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -432,21 +569,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 500;
         let offset = 0;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_four() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -454,21 +599,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 18;
         let offset = 1;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_five() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -476,21 +629,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: true,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 240;
         let offset = 111;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_six() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -498,21 +659,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 93;
         let offset = 9;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_seven() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -520,21 +689,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 9;
         let offset = 16;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_eight() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: true,
             one_byte_char: false,
             one_byte_octal: false,
@@ -542,21 +719,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: false,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 40;
         let offset = 16;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_nine() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -564,21 +749,29 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: true,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 32;
         let offset = 14;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
     #[test]
     fn test_hd_bytes_to_dump_and_offset_ten() {
-        use crate::HdOptions;
+        use crate::DumpOptions;
         use crate::hexdump;
-        let opt: HdOptions = HdOptions {
+        let opt: DumpOptions = DumpOptions {
             canonical: false,
             one_byte_char: false,
             one_byte_octal: false,
@@ -586,16 +779,1585 @@ mod tests {
             two_bytes_dec: false,
             two_bytes_octal: false,
             two_bytes_hex: true,
+            ..Default::default()
         };
         let f1 = "test/test0.bin".to_string();
         let bytes_to_dump = 93;
         let offset = 9;
         println!("hd {} bytes starting at {}", bytes_to_dump, offset);
-        let result = hexdump(f1.clone(), bytes_to_dump, offset, &opt);
+        let result = hexdump(
+            &[f1],
+            &DumpOptions {
+                length: Some(bytes_to_dump),
+                skip: offset,
+                ..opt
+            },
+        );
         assert_eq!(result.is_ok(), true);
         assert_eq!(result.unwrap(), bytes_to_dump);
     }
 
+    #[test]
+    fn test_read_full_short_reads() {
+        use crate::read_full;
+        // Chain returns a short read at the boundary between the two halves
+        let mut reader = std::io::Read::chain(&b"0123456"[..], &b"789abcdef"[..]);
+        let mut buf = [0u8; 10];
+        assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 10);
+        assert_eq!(&buf, b"0123456789");
+        assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 6);
+        assert_eq!(read_full(&mut reader, &mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_dumper_skip_and_length() {
+        use crate::{DumpOptions, DumpSummary, Dumper};
+        let opt: DumpOptions = DumpOptions {
+            canonical: true,
+            one_byte_char: false,
+            one_byte_octal: false,
+            no_squeezing: false,
+            two_bytes_dec: false,
+            two_bytes_octal: false,
+            two_bytes_hex: false,
+            skip: 30,
+            length: Some(20),
+            ..Default::default()
+        };
+        let data: Vec<u8> = (0..100).collect();
+        let dumper = Dumper::new(opt);
+        let mut out = Vec::new();
+        let summary = dumper.dump(&data[..], &mut out).unwrap();
+        let answer = "0000001e  1e 1f 20 21 22 23 24 25  26 27 28 29 2a 2b 2c 2d  |.. !\"#$%&'()*+,-|\n\
+                      0000002e  2e 2f 30 31                                       |./01|\n\
+                      00000032\n";
+        assert_eq!(String::from_utf8(out).unwrap(), answer);
+        assert_eq!(
+            summary,
+            DumpSummary {
+                bytes_dumped: 20,
+                end_address: 0x32,
+                lines_printed: 2,
+                lines_squeezed: 0,
+            }
+        );
+
+        // The same dump seeking instead of reading the skipped bytes
+        let mut out_seek = Vec::new();
+        let summary_seek = dumper
+            .dump_seekable(std::io::Cursor::new(&data), &mut out_seek)
+            .unwrap();
+        assert_eq!(String::from_utf8(out_seek).unwrap(), answer);
+        assert_eq!(summary_seek, summary);
+    }
+
+    #[test]
+    fn test_dumper_offset_past_end() {
+        use crate::{DumpOptions, Dumper};
+        let opt = DumpOptions {
+            skip: 200,
+            ..Default::default()
+        };
+        let data: Vec<u8> = (0..100).collect();
+        let dumper = Dumper::new(opt);
+        assert_eq!(dumper.dump(&data[..], std::io::sink()).is_err(), true);
+        let result = dumper.dump_seekable(std::io::Cursor::new(&data), std::io::sink());
+        assert_eq!(result.is_err(), true);
+    }
+
+    #[test]
+    fn test_dumper_squeeze() {
+        use crate::{DumpOptions, Dumper};
+        let opt = DumpOptions {
+            two_bytes_hex: true,
+            ..Default::default()
+        };
+        let data = [0u8; 64];
+        let mut out = Vec::new();
+        let summary = Dumper::new(opt.clone()).dump(&data[..], &mut out).unwrap();
+        let answer = format!("0000000{}\n*\n0000040\n", "    0000".repeat(8));
+        assert_eq!(String::from_utf8(out).unwrap(), answer);
+        assert_eq!(summary.lines_printed, 1);
+        assert_eq!(summary.lines_squeezed, 3);
+
+        let opt = DumpOptions {
+            no_squeezing: true,
+            ..opt
+        };
+        let mut out = Vec::new();
+        let summary = Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 5);
+        assert_eq!(summary.lines_printed, 4);
+        assert_eq!(summary.lines_squeezed, 0);
+    }
+
+    #[test]
+    fn test_dump_files_writes_only_the_dump() {
+        use crate::{DumpOptions, dump_files};
+        let f1 = std::env::temp_dir().join("hd_dump_files.bin");
+        std::fs::write(&f1, b"0123456789abcdefXY").unwrap();
+        let opt = DumpOptions {
+            canonical: true,
+            skip: 2,
+            length: Some(16),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let summary = dump_files(&[f1.to_string_lossy().to_string()], &opt, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000002  32 33 34 35 36 37 38 39  61 62 63 64 65 66 58 59  |23456789abcdefXY|\n\
+             00000012\n"
+        );
+        assert_eq!(summary.bytes_dumped, 16);
+        assert_eq!(summary.lines_printed, 1);
+        std::fs::remove_file(f1).unwrap();
+    }
+
+    #[test]
+    fn test_dumper_large_input() {
+        use crate::{DumpOptions, Dumper};
+        // Squeezing carries on across the reads from the input
+        let data = vec![0u8; 3 * 64 * 1024 + 5];
+        let mut out = Vec::new();
+        let opt = DumpOptions {
+            canonical: true,
+            ..Default::default()
+        };
+        let summary = Dumper::new(opt.clone()).dump(&data[..], &mut out).unwrap();
+        let answer = format!(
+            "00000000  {zeros}|................|\n*\n00030000  00 00 00 00 00{pad}|.....|\n00030005\n",
+            zeros = "00 00 00 00 00 00 00 00  ".repeat(2),
+            pad = " ".repeat(36)
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), answer);
+        assert_eq!(summary.lines_printed, 2);
+        assert_eq!(summary.lines_squeezed, 3 * 4096 - 1);
+
+        // Every line is printed with the right address, with an odd block size
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let opt = DumpOptions {
+            formats: crate::format::parse_format_strings(&[r#""%06_ax" 7/1 " %02x" "\n""#])
+                .unwrap(),
+            length: Some(99_999),
+            skip: 1,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let summary = Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(summary.bytes_dumped, 99_999);
+        assert_eq!(lines.len(), 99_999 / 7 + 1);
+        assert_eq!(lines[1], "000008 08 09 0a 0b 0c 0d 0e");
+        assert_eq!(lines[14285], "01869c 62 63 64 65         ");
+    }
+
+    #[test]
+    fn test_printable_byte() {
+        use crate::{convert_to_string, printable_byte};
+        assert_eq!(printable_byte(b'a'), b'a');
+        assert_eq!(printable_byte(b' '), b' ');
+        assert_eq!(printable_byte(0x7f), b'.');
+        assert_eq!(printable_byte(0x0a), b'.');
+        assert_eq!(convert_to_string(b"a\x00~\xff"), "a.~.");
+    }
+
+    #[test]
+    fn test_xxd_normal() {
+        use crate::xxd::XxdOptions;
+        use crate::{DumpOptions, Dumper};
+        let data = b"hello world, this is xxd!\x00\xff";
+        let opt = DumpOptions {
+            xxd: Some(XxdOptions::default()),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000000: 6865 6c6c 6f20 776f 726c 642c 2074 6869  hello world, thi\n\
+             00000010: 7320 6973 2078 7864 2100 ff              s is xxd!..\n"
+        );
+
+        let opt = DumpOptions {
+            skip: 2,
+            xxd: Some(XxdOptions {
+                cols: 7,
+                group: 3,
+                upper: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[20..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000002: 786421 00FF       xd!..\n"
+        );
+
+        // xxd doesn't squeeze repeated lines
+        let opt = DumpOptions {
+            xxd: Some(XxdOptions::default()),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&[0u8; 48][..], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_xxd_plain_and_include() {
+        use crate::xxd::{XxdOptions, XxdStyle, c_name};
+        use crate::{DumpOptions, Dumper};
+        let data: Vec<u8> = (0xf0..=0xff).collect();
+        let opt = DumpOptions {
+            xxd: Some(XxdOptions {
+                style: XxdStyle::Plain,
+                cols: 6,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "f0f1f2f3f4f5\nf6f7f8f9fafb\nfcfdfeff\n"
+        );
+
+        let opt = DumpOptions {
+            length: Some(5),
+            xxd: Some(XxdOptions {
+                style: XxdStyle::Include,
+                cols: 4,
+                upper: true,
+                name: Some(c_name("3d-model.bin")),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let summary = Dumper::new(opt.clone()).dump(&data[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "unsigned char __3d_model_bin[] = {\n  0XF0, 0XF1, 0XF2, 0XF3,\n  0XF4\n};\n\
+             unsigned int __3d_model_bin_len = 5;\n"
+        );
+        assert_eq!(summary.bytes_dumped, 5);
+
+        // Standard input has no name, only the bytes are printed
+        let opt = DumpOptions {
+            xxd: Some(XxdOptions {
+                name: None,
+                ..opt.xxd.unwrap()
+            }),
+            ..opt
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[..2], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "  0XF0, 0XF1\n");
+    }
+
+    #[test]
+    fn test_reverse_round_trip() {
+        use crate::reverse::reverse;
+        use crate::xxd::{XxdOptions, XxdStyle};
+        use crate::{DumpOptions, Dumper};
+        let mut data: Vec<u8> = (0..=255).collect();
+        data.extend_from_slice(&[0x55; 100]);
+        data.extend_from_slice(b"odd");
+        let modes = [
+            DumpOptions::default(),
+            DumpOptions {
+                canonical: true,
+                ..Default::default()
+            },
+            DumpOptions {
+                two_bytes_hex: true,
+                ..Default::default()
+            },
+            DumpOptions {
+                xxd: Some(XxdOptions {
+                    cols: 9,
+                    group: 4,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            DumpOptions {
+                xxd: Some(XxdOptions {
+                    style: XxdStyle::Plain,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        ];
+        for opt in modes {
+            let mut dump = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut dump).unwrap();
+            let mut rebuilt = Vec::new();
+            let length = reverse(&dump[..], &mut rebuilt, false).unwrap();
+            assert_eq!(length, data.len() as u64);
+            assert_eq!(rebuilt, data);
+        }
+    }
+
+    #[test]
+    fn test_reverse_gaps() {
+        use crate::error::{EXIT_BAD_DUMP, HdError};
+        use crate::reverse::{reverse, reverse_seekable};
+        // Gaps are zero filled, up to the end address too
+        let dump = "00000004  41 42\n00000010  43\n00000014\n";
+        let mut out = Vec::new();
+        assert_eq!(reverse(dump.as_bytes(), &mut out, false).unwrap(), 0x14);
+        let mut answer = vec![0u8; 0x14];
+        answer[4..6].copy_from_slice(b"AB");
+        answer[0x10] = b'C';
+        assert_eq!(out, answer);
+
+        // An address going backwards can only be followed by seeking
+        let dump = "00000010: 4142\n00000002: 43\n";
+        let err = reverse(dump.as_bytes(), std::io::sink(), false).unwrap_err();
+        assert_eq!(matches!(err, HdError::BadDump { line: 2, .. }), true);
+        assert_eq!(err.exit_code(), EXIT_BAD_DUMP);
+        let mut file = std::io::Cursor::new(vec![b'.'; 0x14]);
+        reverse_seekable(dump.as_bytes(), &mut file, false).unwrap();
+        assert_eq!(file.into_inner(), b"..C.............AB..");
+
+        let err = reverse("not a dump\n".as_bytes(), std::io::sink(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad dump, line 1: not a line of a hex dump"
+        );
+        let mut out = Vec::new();
+        reverse("41 42\n4344\n".as_bytes(), &mut out, true).unwrap();
+        assert_eq!(out, b"ABCD");
+    }
+
+    #[test]
+    fn test_reverse_address_only() {
+        use crate::reverse::reverse;
+
+        // hd -C -s 30 of a 30 byte file prints only the end address
+        let mut out = Vec::new();
+        assert_eq!(
+            reverse("0000001e\n".as_bytes(), &mut out, false).unwrap(),
+            30
+        );
+        assert_eq!(out, vec![0u8; 30]);
+
+        // A first line that isn't an hd or xxd line is still plain hex
+        let plain = "41".repeat(30) + "\n4243\n";
+        let mut out = Vec::new();
+        reverse(plain.as_bytes(), &mut out, false).unwrap();
+        assert_eq!(out, [vec![b'A'; 30], b"BC".to_vec()].concat());
+    }
+
+    #[test]
+    fn test_width() {
+        use crate::format::Mode;
+        use crate::{DumpOptions, Dumper};
+        assert_eq!(
+            Mode::Canonical.format_strings(16, None),
+            [
+                r#""%08.8_Ax\n""#,
+                r#""%08.8_ax  " 8/1 "%02x " "  " 8/1 "%02x ""#,
+                r#""  |" 16/1 "%_p" "|\n""#,
+            ]
+        );
+        assert_eq!(
+            Mode::TwoBytesHex.format_strings(16, None),
+            [r#""%07.7_Ax\n""#, r#""%07.7_ax " 8/2 "   %04x " "\n""#]
+        );
+
+        let data: Vec<u8> = (0x41..0x41 + 11).collect();
+        let opt = DumpOptions {
+            canonical: true,
+            width: Some(8),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000000  41 42 43 44  45 46 47 48  |ABCDEFGH|\n\
+             00000008  49 4a 4b                  |IJK|\n\
+             0000000b\n"
+        );
+
+        let opt = DumpOptions {
+            two_bytes_hex: true,
+            width: Some(4),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0000000    4241    4443\n\
+             0000004    4645    4847\n\
+             0000008    4a49    004b\n\
+             000000b\n"
+        );
+
+        // Squeezing works on lines of any width
+        let opt = DumpOptions {
+            canonical: true,
+            width: Some(32),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let summary = Dumper::new(opt).dump(&[0u8; 100][..], &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "*");
+        assert_eq!(lines[2].len(), "00000060  ".len() + 3 * 32 + 1 + 1 + 6);
+        assert_eq!(summary.lines_squeezed, 2);
+    }
+
+    #[test]
+    fn test_group() {
+        use crate::{DumpOptions, Dumper};
+        let data: Vec<u8> = (1..=20).collect();
+        let dump = |opt: DumpOptions| {
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            dump(DumpOptions {
+                group: Some(4),
+                ..Default::default()
+            }),
+            "0000000 04030201 08070605 0c0b0a09 100f0e0d\n\
+             0000010 14131211                           \n\
+             0000014\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_dec: true,
+                group: Some(1),
+                width: Some(8),
+                length: Some(8),
+                ..Default::default()
+            }),
+            "0000000   001   002   003   004   005   006   007   008\n0000008\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                canonical: true,
+                group: Some(8),
+                length: Some(16),
+                ..Default::default()
+            }),
+            "00000000  0807060504030201  100f0e0d0c0b0a09  |................|\n00000010\n"
+        );
+        // 16-byte groups are 128-bit numbers
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_hex: true,
+                group: Some(16),
+                length: Some(16),
+                ..Default::default()
+            }),
+            "0000000    100f0e0d0c0b0a090807060504030201 \n0000010\n"
+        );
+    }
+
+    #[test]
+    fn test_endian() {
+        use crate::format::{Endian, parse_format_strings};
+        use crate::{DumpOptions, Dumper};
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9a];
+        let dump = |opt: DumpOptions| {
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // The last word is short, its missing low byte reads as zero
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_hex: true,
+                width: Some(6),
+                endian: Endian::Big,
+                ..Default::default()
+            }),
+            "0000000    1234    5678    9a00\n0000005\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                canonical: true,
+                group: Some(4),
+                width: Some(8),
+                endian: Endian::Big,
+                ..Default::default()
+            }),
+            "00000000  12345678  9a000000  |.4Vx.|\n00000005\n"
+        );
+        // Format strings follow the byte order too, signed values included
+        let formats = parse_format_strings(&[r#"2/2 "%d " "\n""#]).unwrap();
+        assert_eq!(
+            dump(DumpOptions {
+                formats: formats.clone(),
+                skip: 1,
+                endian: Endian::Big,
+                ..Default::default()
+            }),
+            "13398 30874\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                formats,
+                skip: 3,
+                ..Default::default()
+            }),
+            "-25992 \n"
+        );
+        assert_eq!(
+            Endian::native() == Endian::Little,
+            cfg!(target_endian = "little")
+        );
+    }
+
+    #[test]
+    fn test_int_modes() {
+        use crate::format::{IntKind, Mode};
+        use crate::{DumpOptions, Dumper};
+        assert_eq!("x4".parse::<Mode>(), Ok(Mode::Int(IntKind::Hex, 4)));
+        assert_eq!("d".parse::<Mode>(), Ok(Mode::Int(IntKind::Signed, 4)));
+        assert_eq!("u8".parse::<Mode>(), Ok(Mode::Int(IntKind::Unsigned, 8)));
+        assert_eq!("o2".parse::<Mode>(), Ok(Mode::Int(IntKind::Octal, 2)));
+        assert_eq!("x3".parse::<Mode>().is_err(), true);
+        assert_eq!("y4".parse::<Mode>().is_err(), true);
+
+        let mut data = vec![0xff; 8];
+        data.extend_from_slice(&[0x01, 0x00, 0x00, 0x80, 0x02]);
+        let dump = |spec: &str| {
+            let opt = DumpOptions {
+                modes: vec![spec.parse().unwrap()],
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // The partial unit at the end reads zeros for the missing bytes
+        assert_eq!(
+            dump("x4"),
+            "0000000 ffffffff ffffffff 80000001 00000002\n000000d\n"
+        );
+        assert_eq!(
+            dump("d4"),
+            "0000000          -1          -1 -2147483647           2\n000000d\n"
+        );
+        assert_eq!(
+            dump("u4"),
+            "0000000 4294967295 4294967295 2147483649          2\n000000d\n"
+        );
+        assert_eq!(
+            dump("o4"),
+            "0000000 37777777777 37777777777 20000000001 00000000002\n000000d\n"
+        );
+        assert_eq!(
+            dump("d8"),
+            "0000000                   -1          10737418241\n000000d\n"
+        );
+        assert_eq!(
+            dump("x8"),
+            "0000000 ffffffffffffffff 0000000280000001\n000000d\n"
+        );
+    }
+
+    #[test]
+    fn test_signed_byte_and_word_modes() {
+        use crate::format::{Endian, IntKind, Mode};
+        use crate::{DumpOptions, Dumper};
+        assert_eq!("dC".parse::<Mode>(), Ok(Mode::Int(IntKind::Signed, 1)));
+        assert_eq!("dS".parse::<Mode>(), Ok(Mode::Int(IntKind::Signed, 2)));
+        assert_eq!("dI".parse::<Mode>(), Ok(Mode::Int(IntKind::Signed, 4)));
+        assert_eq!("dL".parse::<Mode>(), Ok(Mode::Int(IntKind::Signed, 8)));
+
+        let data = [
+            0x80, 0xff, 0x00, 0x01, 0x7f, 0x00, 0x00, 0x80, 0xff, 0x7f, 0x01, 0x00, 0xfe, 0xff,
+            0x00, 0x00, 0x9c,
+        ];
+        let dump = |spec: &str, endian: Endian| {
+            let opt = DumpOptions {
+                modes: vec![spec.parse().unwrap()],
+                endian,
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // Every column is wide enough for the most negative value and its sign,
+        // the odd byte at the end is the low byte of a little-endian word
+        assert_eq!(
+            dump("d1", Endian::Little),
+            "0000000 -128   -1    0    1  127    0    0 -128   -1  127    1    0   -2   -1    0    0\n\
+             0000010 -100                                                                           \n\
+             0000011\n"
+        );
+        assert_eq!(
+            dump("d2", Endian::Little),
+            "0000000   -128    256    127 -32768  32767      1     -2      0\n\
+             0000010    156                                                 \n\
+             0000011\n"
+        );
+        assert_eq!(
+            dump("d2", Endian::Big),
+            "0000000 -32513      1  32512    128   -129    256   -257      0\n\
+             0000010 -25600                                                 \n\
+             0000011\n"
+        );
+    }
+
+    #[test]
+    fn test_float_modes() {
+        use crate::format::{Endian, Mode};
+        use crate::{DumpOptions, Dumper};
+        assert_eq!("f".parse::<Mode>(), Ok(Mode::Float(8, 17)));
+        assert_eq!("f2".parse::<Mode>(), Ok(Mode::Float(2, 5)));
+        assert_eq!("f4".parse::<Mode>(), Ok(Mode::Float(4, 9)));
+        assert_eq!("f16".parse::<Mode>().is_err(), true);
+
+        let dump = |mode: Mode, endian: Endian, data: &[u8]| {
+            let opt = DumpOptions {
+                modes: vec![mode],
+                endian,
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(data, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // 1, -NaN, inf, -0, the smallest subnormal and pi
+        let single = [
+            0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0xc0, 0xff, 0x00, 0x00, 0x80, 0x7f, 0x00, 0x00,
+            0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0xdb, 0x0f, 0x49, 0x40,
+        ];
+        assert_eq!(
+            dump(Mode::Float(4, 9), Endian::Little, &single),
+            concat!(
+                "0000000               1            -nan             inf              -0\n",
+                "0000010  1.40129846e-45      3.14159274                                \n",
+                "0000018\n",
+            )
+        );
+        assert_eq!(
+            dump(Mode::Float(4, 3), Endian::Little, &single[16..]),
+            "0000000   1.4e-45      3.14                    \n0000008\n"
+        );
+        let big: Vec<u8> = [-1.5f64, 1e300]
+            .iter()
+            .flat_map(|f| f.to_be_bytes())
+            .collect();
+        assert_eq!(
+            dump(Mode::Float(8, 17), Endian::Big, &big),
+            "0000000                     -1.5  1.0000000000000001e+300\n0000010\n"
+        );
+        // Half precision: 1, -2, the largest value, the smallest subnormal,
+        // -inf, NaN, -0 and the smallest normal value
+        let half = [
+            0x00, 0x3c, 0x00, 0xc0, 0xff, 0x7b, 0x01, 0x00, 0x00, 0xfc, 0x00, 0x7e, 0x00, 0x80,
+            0x00, 0x04,
+        ];
+        assert_eq!(
+            dump(Mode::Float(2, 5), Endian::Little, &half),
+            "0000000           1          -2       65504  5.9605e-08        -inf         nan          -0  6.1035e-05\n\
+             0000010\n"
+        );
+    }
+
+    #[test]
+    fn test_binary_mode() {
+        use crate::format::{Format, Mode, parse_format_strings};
+        use crate::{DumpOptions, Dumper};
+        let format = Format::new(
+            parse_format_strings(&[r#"1/1 "%b|" 1/1 "%#010b|" 1/2 "%_b|" "%-10_b|""#]).unwrap(),
+        );
+        assert_eq!(format.block_size(), 5);
+        let mut out = Vec::new();
+        format.display(&mut out, &[0x05, 0x05, 0xa5, 0x01, 0x3c], 0);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "101|0b00000101|0000 0001 1010 0101|0011 1100 |"
+        );
+
+        let data = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00AB\xf0";
+        let dump = |opt: DumpOptions| {
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // 8 bytes per line by default, squeezed like the other modes
+        assert_eq!(
+            dump(DumpOptions {
+                modes: vec![Mode::Binary {
+                    nibbles: false,
+                    ascii: false
+                }],
+                ..Default::default()
+            }),
+            "0000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000 00000000\n\
+             *\n\
+             0000010 01000001 01000010 11110000                                             \n\
+             0000013\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                modes: vec![Mode::Binary {
+                    nibbles: true,
+                    ascii: true
+                }],
+                width: Some(4),
+                skip: 14,
+                ..Default::default()
+            }),
+            "000000e 0000 0000 0000 0000 0100 0001 0100 0010  |..AB|\n\
+             0000012 1111 0000                                |.|\n\
+             0000013\n"
+        );
+    }
+
+    #[test]
+    fn test_several_modes() {
+        use crate::format::{IntKind, Mode};
+        use crate::{DumpOptions, Dumper};
+        let opt = DumpOptions {
+            one_byte_char: true,
+            canonical: true,
+            modes: vec![Mode::Int(IntKind::Hex, 4)],
+            ..Default::default()
+        };
+        assert_eq!(
+            opt.modes(),
+            vec![
+                Mode::Canonical,
+                Mode::OneByteChar,
+                Mode::Int(IntKind::Hex, 4)
+            ]
+        );
+        assert_eq!(DumpOptions::default().modes(), vec![Mode::Default]);
+
+        let data = b"01234567012345670123456701234567!";
+        let dump = |opt: DumpOptions| {
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // One row per mode in order, only the first has the address, and
+        // repeated blocks are squeezed as a whole
+        assert_eq!(
+            dump(DumpOptions {
+                modes: vec![Mode::TwoBytesHex, Mode::OneByteChar],
+                width: Some(8),
+                ..Default::default()
+            }),
+            "0000000    3130    3332    3534    3736\n\
+             \x20         0   1   2   3   4   5   6   7\n\
+             *\n\
+             0000020    0021                        \n\
+             \x20         !                            \n\
+             0000021\n"
+        );
+        // The end address is the one of the first mode
+        assert_eq!(
+            dump(DumpOptions {
+                modes: vec![Mode::Canonical, Mode::TwoBytesDec],
+                skip: 30,
+                ..Default::default()
+            }),
+            "0000001e  36 37 21                                          |67!|\n\
+             \x20         14134   00033                                                \n\
+             00000021\n"
+        );
+    }
+
+    #[test]
+    fn test_color() {
+        use crate::color::{ByteClass, byte_class, unit_color};
+        use crate::format::Endian;
+        use crate::{DumpOptions, Dumper};
+        assert_eq!(byte_class(0x00), ByteClass::Null);
+        assert_eq!(byte_class(b'a'), ByteClass::Printable);
+        assert_eq!(byte_class(b' '), ByteClass::Whitespace);
+        assert_eq!(byte_class(b'\r'), ByteClass::Whitespace);
+        assert_eq!(byte_class(0x1b), ByteClass::Control);
+        assert_eq!(byte_class(0x7f), ByteClass::Control);
+        assert_eq!(byte_class(0x80), ByteClass::HighBit);
+        assert_eq!(byte_class(0xff), ByteClass::Ff);
+        assert_eq!(unit_color(b"ab"), Some("\x1b[36m"));
+        assert_eq!(unit_color(b"a\x00"), None);
+
+        let dump = |opt: DumpOptions| {
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&b"A\x00\xff"[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        // Both the hex and ASCII columns are colored, the address isn't
+        assert_eq!(
+            dump(DumpOptions {
+                canonical: true,
+                width: Some(4),
+                color: true,
+                ..Default::default()
+            }),
+            "00000000  \x1b[36m41\x1b[0m \x1b[90m00\x1b[0m  \x1b[31mff\x1b[0m     \
+             |\x1b[36mA\x1b[0m\x1b[90m.\x1b[0m\x1b[31m.\x1b[0m|\n\
+             00000003\n"
+        );
+        // The hex digits of a word of bytes of different classes are colored
+        // byte by byte, the least significant byte last
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_hex: true,
+                width: Some(4),
+                color: true,
+                ..Default::default()
+            }),
+            "0000000    \x1b[90m00\x1b[0m\x1b[36m41\x1b[0m    \x1b[31m00ff\x1b[0m\n0000003\n"
+        );
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_hex: true,
+                width: Some(4),
+                endian: Endian::Big,
+                color: true,
+                ..Default::default()
+            }),
+            "0000000    \x1b[36m41\x1b[0m\x1b[90m00\x1b[0m    \x1b[31mff00\x1b[0m\n0000003\n"
+        );
+        // Other conversions of bytes of different classes aren't colored
+        assert_eq!(
+            dump(DumpOptions {
+                two_bytes_dec: true,
+                width: Some(4),
+                color: true,
+                ..Default::default()
+            }),
+            "0000000   00065   \x1b[31m00255\x1b[0m\n0000003\n"
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        use crate::format::Endian;
+        use crate::search::{FindOptions, Pattern};
+        use crate::{DumpOptions, Dumper};
+        assert_eq!(Pattern::hex("41 ??42").unwrap().len(), 3);
+        assert_eq!(Pattern::hex("4").is_err(), true);
+        assert_eq!(Pattern::hex("4g").is_err(), true);
+        assert_eq!(Pattern::hex(" ").is_err(), true);
+        assert_eq!(
+            Pattern::utf16("hi", Endian::Big),
+            Pattern::hex("0068 0069").unwrap()
+        );
+
+        // Matches at the start, across a line and at the very end
+        let mut data = [b'.'; 80];
+        data[0..2].copy_from_slice(b"AB");
+        data[31..34].copy_from_slice(b"AxB");
+        data[78..80].copy_from_slice(b"AB");
+        let dump = |find: FindOptions, canonical: bool| {
+            let opt = DumpOptions {
+                canonical,
+                skip: 1,
+                find: Some(find),
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            Dumper::new(opt).dump(&data[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let find = FindOptions {
+            pattern: Pattern::hex("41 ?? 42").unwrap(),
+            context: 0,
+            list: true,
+            highlight: false,
+        };
+        assert_eq!(dump(find.clone(), false), "0000001f\n");
+        let find = FindOptions {
+            pattern: Pattern::text("A"),
+            ..find
+        };
+        assert_eq!(dump(find.clone(), false), "0000001f\n0000004e\n");
+        // Addresses start at the skip offset, a match across two lines shows
+        // both of them and without colors a line of carets marks the match
+        let find = FindOptions {
+            pattern: Pattern::hex("41??42").unwrap(),
+            list: false,
+            ..find
+        };
+        assert_eq!(
+            dump(find.clone(), true),
+            concat!(
+                "00000011  2e 2e 2e 2e 2e 2e 2e 2e  2e 2e 2e 2e 2e 2e 41 78  |..............Ax|\n",
+                "                                                     ^^ ^^                 ^^\n",
+                "00000021  42 2e 2e 2e 2e 2e 2e 2e  2e 2e 2e 2e 2e 2e 2e 2e  |B...............|\n",
+                "          ^^                                                 ^\n"
+            )
+        );
+        // The default format marks the whole word that displays a match, and
+        // lines left out between matches are marked with --
+        let find = FindOptions {
+            pattern: Pattern::text("B"),
+            ..find
+        };
+        assert_eq!(
+            dump(find.clone(), false),
+            concat!(
+                "0000001 2e42 2e2e 2e2e 2e2e 2e2e 2e2e 2e2e 2e2e\n",
+                "        ^^^^\n",
+                "--\n",
+                "0000021 2e42 2e2e 2e2e 2e2e 2e2e 2e2e 2e2e 2e2e\n",
+                "        ^^^^\n",
+                "--\n",
+                "0000041 2e2e 2e2e 2e2e 2e2e 2e2e 2e2e 412e 0042\n",
+                "                                           ^^^^\n"
+            )
+        );
+        let find = FindOptions {
+            pattern: Pattern::text("B"),
+            highlight: true,
+            ..find
+        };
+        assert_eq!(
+            dump(find, true),
+            "00000001  \x1b[7m42\x1b[0m 2e 2e 2e 2e 2e 2e 2e  2e 2e 2e 2e 2e 2e 2e 2e  \
+             |\x1b[7mB\x1b[0m...............|\n\
+             --\n\
+             00000021  \x1b[7m42\x1b[0m 2e 2e 2e 2e 2e 2e 2e  2e 2e 2e 2e 2e 2e 2e 2e  \
+             |\x1b[7mB\x1b[0m...............|\n\
+             --\n\
+             00000041  2e 2e 2e 2e 2e 2e 2e 2e  2e 2e 2e 2e 2e 41 \x1b[7m42\x1b[0m     \
+             |.............A\x1b[7mB\x1b[0m|\n"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        use crate::diff::{DiffOptions, diff};
+        let a = b"abcdefgh........12345678";
+        let b = b"abcdXfgh........1234567890";
+        let options = DiffOptions {
+            names: ["a".to_string(), "b".to_string()],
+            width: 8,
+            summary: false,
+            no_squeezing: false,
+            color: false,
+        };
+        let run = |options: &DiffOptions, a: &[u8], b: &[u8], length: Option<usize>| {
+            let mut out = Vec::new();
+            let differences = diff(options, (a, b), &mut out, 0, length).unwrap();
+            (differences, String::from_utf8(out).unwrap())
+        };
+        // The identical lines are squeezed, the left ASCII column is padded
+        // so the right side lines up, and without colors the differing bytes
+        // are marked with '^' on both sides
+        assert_eq!(
+            run(&options, a, b, None),
+            (
+                3,
+                concat!(
+                    "          a                                    b\n",
+                    "00000000  61 62 63 64 65 66 67 68  |abcdefgh|  61 62 63 64 58 66 67 68  |abcdXfgh|\n",
+                    "                      ^^                ^                  ^^                ^\n",
+                    "*\n",
+                    "00000018                           |        |  39 30                    |90|\n",
+                    "                                               ^^ ^^                     ^^\n",
+                )
+                .to_string()
+            )
+        );
+        assert_eq!(run(&options, a, a, None).0, 0);
+        assert_eq!(run(&options, a, b, Some(4)).0, 0);
+
+        let options = DiffOptions {
+            summary: true,
+            ..options
+        };
+        assert_eq!(
+            run(&options, b"aXXbcY", b"a__bc", None),
+            (
+                3,
+                "00000001-00000002  2 bytes differ\n00000005-00000005  1 byte only in a\n"
+                    .to_string()
+            )
+        );
+        let options = DiffOptions {
+            summary: false,
+            color: true,
+            width: 2,
+            ..options
+        };
+        assert_eq!(
+            run(&options, b"a\x00", b"b\x00", None).1,
+            "          a            b\n\
+             00000000  \x1b[7m61\x1b[0m \x1b[90m00\x1b[0m  |\x1b[7ma\x1b[0m\x1b[90m.\x1b[0m|  \
+             \x1b[7m62\x1b[0m \x1b[90m00\x1b[0m  |\x1b[7mb\x1b[0m\x1b[90m.\x1b[0m|\n"
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        use crate::strings::{Encoding, StringsOptions, dump_strings};
+        let run = |options: &StringsOptions, data: &[u8], offset: usize, length: Option<usize>| {
+            let mut out = Vec::new();
+            let summary = dump_strings(options, data, &mut out, offset, length).unwrap();
+            (summary.lines_printed, String::from_utf8(out).unwrap())
+        };
+        let options = StringsOptions {
+            min_len: 4,
+            encoding: Encoding::Ascii,
+            decimal: false,
+        };
+        let data = b"\x00abc\x01hello world\x7f\xc3\xa9t\xc3\xa9s\xffdone";
+        assert_eq!(
+            run(&options, data, 0, None),
+            (2, "00000005  hello world\n00000018  done\n".to_string())
+        );
+        // Offsets are addresses, the run cut short by -n is still printed
+        assert_eq!(
+            run(&options, &data[8..], 8, Some(6)),
+            (1, "00000008  lo wor\n".to_string())
+        );
+        let decimal = StringsOptions {
+            decimal: true,
+            ..options.clone()
+        };
+        assert_eq!(
+            run(&decimal, data, 0, None),
+            (2, "00000005  hello world\n00000024  done\n".to_string())
+        );
+
+        let utf8 = StringsOptions {
+            encoding: Encoding::Utf8,
+            ..options.clone()
+        };
+        assert_eq!(
+            run(&utf8, data, 0, None),
+            (
+                3,
+                "00000005  hello world\n00000011  étés\n00000018  done\n".to_string()
+            )
+        );
+        // A sequence cut short ends the run
+        assert_eq!(
+            run(&utf8, b"caf\xe2\x82xyz\xe2\x82\xac", 0, None).1,
+            "00000005  xyz€\n".to_string()
+        );
+
+        // UTF-16 only takes the code units of printable ASCII, at even and
+        // odd offsets
+        let utf16 = StringsOptions {
+            encoding: Encoding::Utf16Le,
+            ..options.clone()
+        };
+        let data = b"h\x00e\x00l\x00l\x00o\x00\x00\x00\xe9\x00t\x00\xe9\x00s\x00\x01";
+        assert_eq!(
+            run(&utf16, data, 0, None).1,
+            "00000000  hello\n".to_string()
+        );
+        let data = b"\x01a\x00b\x00c\x00d\x00\xffw\x00x\x00y\x00z\x00";
+        assert_eq!(
+            run(&utf16, data, 0, None).1,
+            "00000001  abcd\n0000000a  wxyz\n".to_string()
+        );
+        // Noise isn't read as text
+        let mut seed: u32 = 0x1234_5678;
+        let noise: Vec<u8> = (0..0x10000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect();
+        assert_eq!(run(&utf16, &noise, 0, None), (0, String::new()));
+        let utf16 = StringsOptions {
+            encoding: Encoding::Utf16Be,
+            ..options
+        };
+        assert_eq!(run(&utf16, &noise, 0, None), (0, String::new()));
+        assert_eq!(
+            run(&utf16, b"\x00a\x00b\x00c\x00d\xd8\x00", 0, None).1,
+            "00000000  abcd\n".to_string()
+        );
+    }
+
+    #[test]
+    fn test_entropy() {
+        use crate::entropy::{EntropyOptions, dump_entropy, shannon_entropy};
+        let mut counts = [0usize; 256];
+        assert_eq!(shannon_entropy(&counts), 0.0);
+        counts[0x41] = 10;
+        assert_eq!(shannon_entropy(&counts), 0.0);
+        counts[0x42] = 10;
+        assert_eq!(shannon_entropy(&counts), 1.0);
+        assert_eq!(shannon_entropy(&[3; 256]), 8.0);
+
+        let run = |options: &EntropyOptions, data: &[u8], offset: usize, length: Option<usize>| {
+            let mut out = Vec::new();
+            dump_entropy(options, data, &mut out, offset, length).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let options = EntropyOptions {
+            entropy: true,
+            histogram: false,
+            block_size: 4,
+        };
+        // A block of one value, a block of two and a partial block
+        let data = b"\0\0\0\0ABABxy";
+        assert_eq!(
+            run(&options, data, 0, None),
+            "00000000  0.000\n\
+             00000004  1.000  ████\n\
+             00000008  1.000  ████\n\
+             \n\
+             bytes     10\n\
+             entropy   2.122 bits per byte\n\
+             distinct  5 byte values\n"
+        );
+        // Blocks start at the first byte counted, -n limits the bytes
+        assert_eq!(
+            run(&options, &data[4..], 4, Some(4)),
+            "00000004  1.000  ████\n\n\
+             bytes     4\nentropy   1.000 bits per byte\ndistinct  2 byte values\n"
+        );
+        // No input, no graph
+        assert_eq!(
+            run(&options, b"", 0, None),
+            "bytes     0\nentropy   0.000 bits per byte\ndistinct  0 byte values\n"
+        );
+
+        let options = EntropyOptions {
+            entropy: false,
+            histogram: true,
+            block_size: 4,
+        };
+        assert_eq!(
+            run(&options, b"AAAB", 0, None),
+            format!(
+                "41           3   75.00%  {}\n42           1   25.00%  {}\n",
+                "█".repeat(32),
+                "█".repeat(10) + "▋"
+            )
+        );
+        assert_eq!(run(&options, b"", 0, None), "");
+    }
+
+    #[test]
+    fn test_sizes() {
+        use crate::size::{Offset, parse_offset, parse_size};
+        use crate::{DumpOptions, Dumper};
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert_eq!(parse_size("0x1000"), Ok(4096));
+        assert_eq!(parse_size("010000"), Ok(4096));
+        assert_eq!(parse_size("0"), Ok(0));
+        assert_eq!(parse_size("4k"), Ok(4096));
+        assert_eq!(parse_size("4KiB"), Ok(4096));
+        assert_eq!(parse_size("4kB"), Ok(4000));
+        assert_eq!(parse_size("8b"), Ok(4096));
+        assert_eq!(parse_size("0x1b"), Ok(0x1b));
+        assert_eq!(parse_size("0x2MiB"), Ok(2 << 20));
+        assert_eq!(parse_size("3G"), Ok(3 << 30));
+        // T doesn't fit in 32 bits, so it is only a size on 64-bit targets
+        assert_eq!(
+            parse_size("2TiB"),
+            usize::try_from(2u64 << 40)
+                .map_err(|_| format!("'2TiB' is too big, sizes go up to {}", usize::MAX))
+        );
+        assert_eq!(parse_size("08").is_err(), true);
+        assert_eq!(parse_size("4q").is_err(), true);
+        assert_eq!(parse_size("").is_err(), true);
+        assert_eq!(
+            parse_size("99999999999999999999"),
+            Err(format!(
+                "'99999999999999999999' is too big, sizes go up to {}",
+                usize::MAX
+            ))
+        );
+        assert_eq!(parse_size("17179869184G").is_err(), true);
+        assert_eq!(parse_offset("-64"), Ok(Offset::End(64)));
+        assert_eq!(parse_offset("-1k"), Ok(Offset::End(1024)));
+        assert_eq!(parse_offset("+0x10"), Ok(Offset::Start(16)));
+        assert_eq!(parse_offset("512"), Ok(Offset::Start(512)));
+        assert_eq!(parse_offset("--1").is_err(), true);
+
+        // -s with a minus sign, from a source that can seek and one that can't
+        let data: Vec<u8> = (0..40).collect();
+        let options = DumpOptions {
+            canonical: true,
+            skip: 8,
+            skip_from_end: true,
+            ..Default::default()
+        };
+        let expected = "00000020  20 21 22 23 24 25 26 27                           | !\"#$%&'|\n\
+                        00000028\n";
+        let dumper = Dumper::new(options.clone());
+        let mut out = Vec::new();
+        dumper.dump(&data[..], &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        let mut out = Vec::new();
+        dumper
+            .dump_seekable(std::io::Cursor::new(&data), &mut out)
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        // More than the whole input starts at the beginning
+        let dumper = Dumper::new(DumpOptions {
+            skip: 100,
+            length: Some(4),
+            ..options
+        });
+        let mut out = Vec::new();
+        let summary = dumper.dump(&data[..], &mut out).unwrap();
+        assert_eq!(summary.bytes_dumped, 4);
+        assert_eq!(summary.end_address, 4);
+    }
+
+    #[test]
+    fn test_ranges() {
+        use crate::error::HdError;
+        use crate::size::{Region, parse_range};
+        use crate::{DumpOptions, dump_files};
+        assert_eq!(
+            parse_range("0x200:0x400"),
+            Ok(Region {
+                start: 0x200,
+                length: Some(0x200)
+            })
+        );
+        assert_eq!(
+            parse_range("1k+64"),
+            Ok(Region {
+                start: 1024,
+                length: Some(64)
+            })
+        );
+        assert_eq!(
+            parse_range("16:"),
+            Ok(Region {
+                start: 16,
+                length: None
+            })
+        );
+        assert_eq!(parse_range("8:4").is_err(), true);
+        assert_eq!(parse_range("8").is_err(), true);
+        assert_eq!(parse_range("8+x").is_err(), true);
+
+        let f1 = std::env::temp_dir().join("hd_ranges.bin");
+        let data: Vec<u8> = (0x40..0x80).collect();
+        std::fs::write(&f1, &data).unwrap();
+        let files = [f1.to_string_lossy().to_string()];
+        // Addresses stay absolute and a region before the one above it is
+        // read again from the start
+        let opt = DumpOptions {
+            canonical: true,
+            ranges: vec![
+                parse_range("0x10+4").unwrap(),
+                parse_range("0x30:0x34").unwrap(),
+                parse_range("8+2").unwrap(),
+            ],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let summary = dump_files(&files, &opt, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "00000010  50 51 52 53                                       |PQRS|\n\
+             00000014\n\
+             --\n\
+             00000030  70 71 72 73                                       |pqrs|\n\
+             00000034\n\
+             --\n\
+             00000008  48 49                                             |HI|\n\
+             0000000a\n"
+        );
+        assert_eq!(summary.bytes_dumped, 10);
+        assert_eq!(summary.lines_printed, 3);
+
+        // A region past the end of the input
+        let opt = DumpOptions {
+            ranges: vec![parse_range("0+4").unwrap(), parse_range("0x50+4").unwrap()],
+            ..opt
+        };
+        let result = dump_files(&files, &opt, std::io::sink());
+        assert_eq!(
+            matches!(
+                result,
+                Err(HdError::OffsetPastEnd {
+                    offset: 0x50,
+                    input_length: 0x40
+                })
+            ),
+            true
+        );
+        std::fs::remove_file(f1).unwrap();
+    }
+
+    #[test]
+    fn test_address_only_format() {
+        use crate::format::parse_format_string;
+        use crate::{DumpOptions, Dumper};
+        // Nothing displays the bytes, so identical ones aren't squeezed
+        let run = |format: &str| {
+            let options = DumpOptions {
+                formats: vec![parse_format_string(format).unwrap()],
+                ..Default::default()
+            };
+            let mut out = Vec::new();
+            Dumper::new(options).dump(&b"aaaab"[..], &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(run(r#""%_Ad\n""#), "5\n");
+        assert_eq!(run(r#""%_ad\n""#), "0\n1\n2\n3\n4\n");
+    }
+
+    #[test]
+    fn test_multi_reader_continuous_stream() {
+        use crate::input::MultiReader;
+        use std::io::Read;
+        let dir = std::env::temp_dir();
+        let f1 = dir.join("hd_multi_reader_one.bin");
+        let f2 = dir.join("hd_multi_reader_two.bin");
+        std::fs::write(&f1, b"ABCDEFGHIJ").unwrap();
+        std::fs::write(&f2, b"klmnopqrstuvwxyz").unwrap();
+        let files = vec![
+            f1.to_string_lossy().to_string(),
+            "test/does_not_exist.bin".to_string(),
+            f2.to_string_lossy().to_string(),
+        ];
+        let mut input = MultiReader::new(&files);
+        // The skip runs over the whole first file and into the second one
+        assert_eq!(input.skip(12), 12);
+        let mut rest = Vec::new();
+        input.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"mnopqrstuvwxyz");
+        assert_eq!(input.errors.len(), 1);
+        assert_eq!(
+            matches!(input.errors[0], crate::error::HdError::MissingPath(_)),
+            true
+        );
+
+        let mut input = MultiReader::new(&files);
+        assert_eq!(input.skip(100), 26);
+        std::fs::remove_file(f1).unwrap();
+        std::fs::remove_file(f2).unwrap();
+    }
+
+    #[test]
+    fn test_hd_error_exit_codes() {
+        use crate::DumpOptions;
+        use crate::error::{
+            EXIT_MISSING_PATH, EXIT_OFFSET_PAST_END, EXIT_PERMISSION_DENIED, EXIT_READ, HdError,
+        };
+        use crate::hexdump;
+        let opt = DumpOptions::default();
+        let f1 = "test/test0_does_not_exist.bin".to_string();
+        let err = hexdump(std::slice::from_ref(&f1), &opt).unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_MISSING_PATH);
+        match err {
+            HdError::Inputs(errors) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(
+                    errors[0].to_string(),
+                    format!("{}: No such file or directory", f1)
+                );
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let err = HdError::from_io(
+            "f",
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+        );
+        assert_eq!(err.exit_code(), EXIT_PERMISSION_DENIED);
+        let err = HdError::from_io("f", std::io::Error::from(std::io::ErrorKind::InvalidData));
+        assert_eq!(err.exit_code(), EXIT_READ);
+
+        let opt = DumpOptions {
+            skip: 200,
+            ..Default::default()
+        };
+        let err = crate::Dumper::new(opt)
+            .dump(&[0u8; 10][..], std::io::sink())
+            .unwrap_err();
+        assert_eq!(err.exit_code(), EXIT_OFFSET_PAST_END);
+    }
+
+    #[test]
+    fn test_dumper_write_failure() {
+        use crate::error::{EXIT_WRITE, HdError};
+        use crate::{DumpOptions, Dumper};
+        struct FailingWriter;
+        impl std::io::Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = Dumper::new(DumpOptions::default())
+            .dump(&b"hello"[..], FailingWriter)
+            .unwrap_err();
+        assert_eq!(matches!(err, HdError::Write(_)), true);
+        assert_eq!(err.exit_code(), EXIT_WRITE);
+    }
+
+    #[test]
+    fn test_format_canonical() {
+        use crate::format::{Format, Mode, parse_format_strings};
+        let format =
+            Format::new(parse_format_strings(&Mode::Canonical.format_strings(16, None)).unwrap());
+        assert_eq!(format.block_size(), 16);
+        let mut out = Vec::new();
+        format.display(&mut out, b"Hello, world!\n\x00\x01", 0x10);
+        format.display(&mut out, b"abc", 0x20);
+        format.display_end(&mut out, 0x23);
+        let answer = "00000010  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 00 01  |Hello, world!...|\n\
+                      00000020  61 62 63                                          |abc|\n\
+                      00000023\n";
+        assert_eq!(String::from_utf8(out).unwrap(), answer);
+    }
+
+    #[test]
+    fn test_format_two_bytes_partial() {
+        use crate::format::{Format, Mode, parse_format_strings};
+        let format =
+            Format::new(parse_format_strings(&Mode::Default.format_strings(16, None)).unwrap());
+        let mut out = Vec::new();
+        format.display(&mut out, &[0x01, 0x02, 0x03], 0);
+        let answer = format!("0000000 0201 0003{}\n", " ".repeat(30));
+        assert_eq!(String::from_utf8(out).unwrap(), answer);
+    }
+
+    #[test]
+    fn test_format_special_conversions() {
+        use crate::format::{Format, parse_format_strings};
+        let format =
+            Format::new(parse_format_strings(&[r#""%_ad:" 4/1 " %3_c" 4/1 " %_u" "\n""#]).unwrap());
+        assert_eq!(format.block_size(), 8);
+        let mut out = Vec::new();
+        format.display(
+            &mut out,
+            &[0x00, 0x0a, 0x41, 0x80, 0x1b, 0x20, 0x7f, 0xff],
+            8,
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "8:  \\0  \\n   A 200 esc   del ff\n"
+        );
+    }
+
+    #[test]
+    fn test_format_printf_conversions() {
+        use crate::format::{Format, parse_format_strings};
+        let format = Format::new(
+            parse_format_strings(&[
+                r#"1/2 "%-6d|" 1/2 "%+d|" 1/1 "%#o|" 1/1 "%#X|" 1/1 "%c|" "%3.2s|""#,
+            ])
+            .unwrap(),
+        );
+        assert_eq!(format.block_size(), 9);
+        let mut out = Vec::new();
+        format.display(
+            &mut out,
+            &[0xfe, 0xff, 0x10, 0x00, 0x08, 0xab, b'z', b'o', b'k'],
+            0,
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "-2    |+16|010|0XAB|z| ok|"
+        );
+    }
+
+    #[test]
+    fn test_format_float_conversions() {
+        use crate::format::{Format, parse_format_strings};
+        let format = Format::new(
+            parse_format_strings(&[
+                r#"1/8 "%e|" 1/8 "%.2f|" 1/8 "%g|" 1/8 "%+08.3G|" 1/4 "%#.0f|" 1/4 "%05f|""#,
+            ])
+            .unwrap(),
+        );
+        assert_eq!(format.block_size(), 40);
+        let mut data = Vec::new();
+        for value in [1234.5f64, -0.005, 0.0001, 1e-10] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [3f32, f32::NEG_INFINITY] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut out = Vec::new();
+        format.display(&mut out, &data, 0);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1.234500e+03|-0.01|0.0001|+001E-10|3.| -inf|"
+        );
+    }
+
+    #[test]
+    fn test_format_repeat_last_unit() {
+        use crate::format::{Format, parse_format_strings};
+        // The last unit has no iteration count so it is repeated to fill the block
+        let format =
+            Format::new(parse_format_strings(&[r#"4/1 "%02x""#, r#"/1 "%02x ""#]).unwrap());
+        assert_eq!(format.block_size(), 4);
+        let mut out = Vec::new();
+        format.display(&mut out, &[1, 2, 3, 4], 0);
+        assert_eq!(String::from_utf8(out).unwrap(), "0102030401 02 03 04");
+    }
+
+    #[test]
+    fn test_format_errors() {
+        use crate::format::parse_format_string;
+        let err = parse_format_string(r#"16/1 "%02x "#).unwrap_err();
+        assert_eq!(err.column, 6);
+        let err = parse_format_string(r#"1/3 "%x""#).unwrap_err();
+        assert_eq!(err.column, 6);
+        let err = parse_format_string(r#""%07.7_ay""#).unwrap_err();
+        assert_eq!(err.column, 2);
+        let err = parse_format_string(r#"2/1 "%x %x""#).unwrap_err();
+        assert_eq!(err.column, 9);
+        assert_eq!(parse_format_string(r#""%s""#).is_err(), true);
+        assert_eq!(parse_format_string(r#"4 / "%x""#).is_err(), true);
+        assert_eq!(parse_format_string(r#"0/1 "%x""#).is_err(), true);
+        assert_eq!(parse_format_string(r#"8/1 "%02x%%""#).is_ok(), true);
+    }
+
+    #[test]
+    fn test_format_file() {
+        use crate::format::{Format, parse_format_file};
+        let contents = "# firmware header\n\
+                        \n\
+                        \x20 \"magic: \" 4/1 \"%02x\" \"\\n\"\n\
+                        \x20 # version\n\
+                        \"version: \" 1/2 \"%u\" \"\\n\"\n";
+        let strings = parse_format_file("header.fmt", contents).unwrap();
+        assert_eq!(strings.len(), 2);
+        let format = Format::new(strings);
+        let mut out = Vec::new();
+        format.display(&mut out, &[0x7f, b'E', b'L', b'F'], 0);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "magic: 7f454c46\nversion: 17791\n"
+        );
+    }
+
+    #[test]
+    fn test_format_file_error() {
+        use crate::format::parse_format_file;
+        let contents = "# comment\n\"%02x\"\n  4/1 \"%q\"\n";
+        let err = parse_format_file("bad.fmt", contents).unwrap_err();
+        assert_eq!(err.file, Some(("bad.fmt".to_string(), 3)));
+        assert_eq!(err.column, 8);
+        assert_eq!(err.to_string(), "bad.fmt:3:8: bad conversion character %q");
+    }
+
     #[test]
     fn test_hd_against_hexdump_from_gnu() {
         println!("Run this command:");
@@ -0,0 +1,178 @@
+//! xxd(1) compatible output
+//!
+//! The normal and plain xxd layouts are built as format strings and dumped
+//! like any other display mode.  The C include layout needs a comma between
+//! bytes but not after the last one, so it has its own dump loop.
+use crate::error::HdError;
+use crate::format::{self, FormatString};
+use crate::read_full;
+use std::io::{Read, Write};
+
+/// Default columns per line for the normal layout
+pub const DEFAULT_COLS: usize = 16;
+/// Default columns per line for -p
+pub const DEFAULT_PLAIN_COLS: usize = 30;
+/// Default columns per line for -i
+pub const DEFAULT_INCLUDE_COLS: usize = 12;
+/// Default bytes per group for the normal layout
+pub const DEFAULT_GROUP: usize = 2;
+/// Largest number of columns xxd accepts
+pub const MAX_COLS: usize = 256;
+
+/// The layouts xxd can print
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum XxdStyle {
+    /// `00000000: 7f45 4c46 0201 0100  .ELF....`
+    #[default]
+    Normal,
+    /// -p continuous hex digits with no address or ASCII column
+    Plain,
+    /// -i C include file, `0x7f, 0x45, ...`
+    Include,
+}
+
+/// Options for --xxd
+#[derive(Clone, Debug, PartialEq)]
+pub struct XxdOptions {
+    /// The layout to print
+    pub style: XxdStyle,
+    /// -c bytes per line
+    pub cols: usize,
+    /// -g bytes per group, 0 for a single group per line
+    pub group: usize,
+    /// -u upper case hex digits
+    pub upper: bool,
+    /// -i variable name, the header and footer are only printed when set
+    pub name: Option<String>,
+}
+
+impl Default for XxdOptions {
+    fn default() -> XxdOptions {
+        XxdOptions {
+            style: XxdStyle::Normal,
+            cols: DEFAULT_COLS,
+            group: DEFAULT_GROUP,
+            upper: false,
+            name: None,
+        }
+    }
+}
+
+impl XxdOptions {
+    /// The format strings for the normal and plain layouts
+    ///
+    /// Return:
+    ///   The format strings, empty for the include layout
+    pub fn format_strings(&self) -> Vec<FormatString> {
+        let hex = if self.upper { "X" } else { "x" };
+        let cols = self.cols.max(1);
+        let texts = match self.style {
+            XxdStyle::Normal => {
+                let group = if self.group == 0 {
+                    cols
+                } else {
+                    self.group.min(cols)
+                };
+                let mut text = String::from(r#""%08.8_ax: ""#);
+                let mut left = cols;
+                while left > 0 {
+                    let n = left.min(group);
+                    text.push_str(&format!(r#" {}/1 "%02{}" " ""#, n, hex));
+                    left -= n;
+                }
+                // The ASCII column starts over at the beginning of the line
+                vec![text, format!(r#"" " {}/1 "%_p" "\n""#, cols)]
+            }
+            // %.2x prints nothing past the end of the input, unlike %02x
+            XxdStyle::Plain => vec![format!(r#"{}/1 "%.2{}" "\n""#, cols, hex)],
+            XxdStyle::Include => Vec::new(),
+        };
+        format::parse_format_strings(&texts).expect("xxd format strings are valid")
+    }
+}
+
+/// Turn a file name into a C identifier the way xxd -i does
+///
+/// Args:
+///   file - The name of the input file
+/// Return:
+///   The name with every character that isn't a letter or digit replaced by
+///   '_', with "__" in front if it starts with a digit
+pub fn c_name(file: &str) -> String {
+    let mut name: String = file
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "__");
+    }
+    name
+}
+
+/// Dump the input as a C include file
+///
+/// Args:
+///   options - The xxd options
+///   reader - The input, already positioned at the first byte to dump
+///   writer - The sink the dump is written to
+///   length - Number of bytes to dump, None for everything up to the end
+/// Return:
+///   Result(bytes_dumped) if success, or Err(HdError) if reading or writing
+///   fails
+pub fn dump_include<R: Read, W: Write>(
+    options: &XxdOptions,
+    mut reader: R,
+    mut writer: W,
+    length: Option<usize>,
+) -> Result<usize, HdError> {
+    let cols = options.cols.max(1);
+    let (prefix, digits) = if options.upper {
+        ("0X", b"0123456789ABCDEF")
+    } else {
+        ("0x", b"0123456789abcdef")
+    };
+    let mut text: Vec<u8> = Vec::new();
+    if let Some(name) = &options.name {
+        text.extend_from_slice(format!("unsigned char {}[] = {{\n", name).as_bytes());
+    }
+    let mut line: Vec<u8> = vec![0; cols];
+    let mut left = length.unwrap_or(usize::MAX);
+    let mut total: usize = 0;
+    while left > 0 {
+        let want = cols.min(left);
+        let bytes_read = read_full(&mut reader, &mut line[..want])
+            .map_err(|source| HdError::Read { path: None, source })?;
+        if bytes_read == 0 {
+            break;
+        }
+        if total > 0 {
+            text.extend_from_slice(b",\n");
+        }
+        text.extend_from_slice(b" ");
+        for (i, byte) in line[..bytes_read].iter().enumerate() {
+            if i > 0 {
+                text.push(b',');
+            }
+            text.push(b' ');
+            text.extend_from_slice(prefix.as_bytes());
+            text.push(digits[(byte >> 4) as usize]);
+            text.push(digits[(byte & 0xf) as usize]);
+        }
+        writer.write_all(&text).map_err(HdError::Write)?;
+        text.clear();
+        total += bytes_read;
+        left -= bytes_read;
+        if bytes_read < want {
+            break;
+        }
+    }
+    if total > 0 {
+        text.push(b'\n');
+    }
+    if let Some(name) = &options.name {
+        text.extend_from_slice(format!("}};\nunsigned int {}_len = {};\n", name, total).as_bytes());
+    }
+    writer.write_all(&text).map_err(HdError::Write)?;
+    writer.flush().map_err(HdError::Write)?;
+    Ok(total)
+}